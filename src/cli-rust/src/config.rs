@@ -0,0 +1,93 @@
+//! Cluster, payer and program settings, read from the Solana CLI config the
+//! way the JavaScript client's `utils.ts` does
+
+use clap::ArgMatches;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Keypair, Signer},
+};
+use std::{error::Error, str::FromStr};
+
+/// Cluster used when the CLI config doesn't name one
+const DEFAULT_RPC_URL: &str = "http://localhost:8899";
+
+/// Keypair of the deployed program, written by
+/// `solana program deploy dist/program/helloworld.so`
+const PROGRAM_KEYPAIR_PATH: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/../../dist/program/helloworld-keypair.json"
+);
+
+/// Settings every command needs
+pub struct Config {
+    /// Cluster to send transactions to
+    pub json_rpc_url: String,
+    /// Signs and pays for every transaction
+    pub payer: Keypair,
+    /// Address of the deployed split program
+    pub program_id: Pubkey,
+}
+
+impl Config {
+    /// Loads the settings, preferring command line arguments over the CLI
+    /// config file
+    pub fn load(matches: &ArgMatches) -> Result<Self, Box<dyn Error>> {
+        let cli_config = match matches.value_of("config") {
+            Some(config_file) => Some(solana_cli_config::Config::load(config_file)?),
+            None => solana_cli_config::CONFIG_FILE
+                .as_ref()
+                .and_then(|config_file| solana_cli_config::Config::load(config_file).ok()),
+        };
+
+        let json_rpc_url = match (matches.value_of("url"), &cli_config) {
+            (Some(url), _) => url.to_string(),
+            (None, Some(cli_config)) if !cli_config.json_rpc_url.is_empty() => {
+                cli_config.json_rpc_url.clone()
+            }
+            _ => {
+                eprintln!(
+                    "Failed to read RPC url from CLI config file, falling back to {}",
+                    DEFAULT_RPC_URL
+                );
+                DEFAULT_RPC_URL.to_string()
+            }
+        };
+
+        let keypair_path = match (matches.value_of("keypair"), cli_config) {
+            (Some(path), _) => path.to_string(),
+            (None, Some(cli_config)) => cli_config.keypair_path,
+            (None, None) => solana_cli_config::Config::default().keypair_path,
+        };
+        let payer = read_keypair_file(&keypair_path)
+            .map_err(|err| format!("Failed to read keypair at '{}': {}", keypair_path, err))?;
+
+        let program_id = match matches.value_of("program_id") {
+            Some(program_id) => pubkey_or_keypair(program_id)?,
+            None => read_keypair_file(PROGRAM_KEYPAIR_PATH)
+                .map_err(|err| {
+                    format!(
+                        "Failed to read program keypair at '{}': {}. Program may need to be \
+                         deployed with `solana program deploy dist/program/helloworld.so`",
+                        PROGRAM_KEYPAIR_PATH, err
+                    )
+                })?
+                .pubkey(),
+        };
+
+        Ok(Self {
+            json_rpc_url,
+            payer,
+            program_id,
+        })
+    }
+}
+
+/// Parses `value` as an address, or reads the keypair file it names
+fn pubkey_or_keypair(value: &str) -> Result<Pubkey, Box<dyn Error>> {
+    match Pubkey::from_str(value) {
+        Ok(pubkey) => Ok(pubkey),
+        Err(_) => Ok(read_keypair_file(value)
+            .map_err(|err| format!("Failed to read keypair at '{}': {}", value, err))?
+            .pubkey()),
+    }
+}
@@ -0,0 +1,476 @@
+//! Command line client that builds, signs and sends splits
+
+mod config;
+mod payees;
+
+use crate::{config::Config, payees::Payee};
+use clap::{
+    crate_description, crate_name, crate_version, value_t_or_exit, App, AppSettings, Arg,
+    ArgMatches, SubCommand,
+};
+use helloworld::{
+    chunk::{chunk_split, Chunk},
+    event::{parse_logs, PayeeAmount, SplitEvent},
+    instruction,
+    split::{DuplicatePolicy, DustPolicy, MemoPolicy, RemainderPolicy, Weights, MAX_PAYEES},
+    state::SplitRecord,
+};
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcTransactionConfig};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::hash,
+    instruction::Instruction,
+    native_token::lamports_to_sol,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use solana_transaction_status::UiTransactionEncoding;
+use std::{error::Error, process::exit};
+
+fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new(crate_name!())
+        .version(crate_version!())
+        .about(crate_description!())
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::with_name("config")
+                .long("config")
+                .value_name("PATH")
+                .takes_value(true)
+                .global(true)
+                .help("Solana CLI config file [default: ~/.config/solana/cli/config.yml]"),
+        )
+        .arg(
+            Arg::with_name("url")
+                .long("url")
+                .short("u")
+                .value_name("URL")
+                .takes_value(true)
+                .global(true)
+                .help("JSON RPC URL of the cluster, overriding the config file"),
+        )
+        .arg(
+            Arg::with_name("keypair")
+                .long("keypair")
+                .short("k")
+                .value_name("PATH")
+                .takes_value(true)
+                .global(true)
+                .help("Keypair of the payer, overriding the config file"),
+        )
+        .arg(
+            Arg::with_name("program_id")
+                .long("program-id")
+                .value_name("ADDRESS_OR_KEYPAIR")
+                .takes_value(true)
+                .global(true)
+                .help("Split program [default: dist/program/helloworld-keypair.json]"),
+        )
+        .subcommand(
+            SubCommand::with_name("split")
+                .about("Split lamports from the payer between payees")
+                .arg(
+                    Arg::with_name("amount")
+                        .value_name("LAMPORTS")
+                        .required(true)
+                        .validator(|value| {
+                            value
+                                .parse::<u64>()
+                                .map(|_| ())
+                                .map_err(|_| format!("Invalid amount '{}'", value))
+                        })
+                        .help("Lamports to split"),
+                )
+                .arg(
+                    Arg::with_name("payees")
+                        .value_name("PAYEE[:WEIGHT]")
+                        .multiple(true)
+                        .validator(|value| value.parse::<Payee>().map(|_| ()))
+                        .help("Payee addresses, each with an optional weight"),
+                )
+                .arg(
+                    Arg::with_name("csv")
+                        .long("csv")
+                        .value_name("PATH")
+                        .takes_value(true)
+                        .help("CSV file of PAYEE[,WEIGHT] rows, added after the payee arguments"),
+                )
+                .arg(
+                    Arg::with_name("basis_points")
+                        .long("basis-points")
+                        .help("Read weights as basis points adding up to 10000"),
+                )
+                .arg(
+                    Arg::with_name("remainder")
+                        .long("remainder")
+                        .value_name("POLICY")
+                        .takes_value(true)
+                        .possible_values(&[
+                            "payer",
+                            "first-payee",
+                            "last-payee",
+                            "largest-remainder",
+                            "reject-uneven",
+                        ])
+                        .default_value("payer")
+                        .help("Where lamports left over from rounding go"),
+                )
+                .arg(
+                    Arg::with_name("duplicates")
+                        .long("duplicates")
+                        .value_name("POLICY")
+                        .takes_value(true)
+                        .possible_values(&["reject", "merge"])
+                        .default_value("reject")
+                        .help("How a payee listed more than once is handled"),
+                )
+                .arg(
+                    Arg::with_name("dust")
+                        .long("dust")
+                        .value_name("POLICY")
+                        .takes_value(true)
+                        .possible_values(&["allow", "refund", "redistribute", "reject"])
+                        .default_value("allow")
+                        .help("How shares that leave a payee below rent exemption are handled"),
+                )
+                .arg(
+                    Arg::with_name("memo")
+                        .long("memo")
+                        .value_name("TEXT")
+                        .takes_value(true)
+                        .help("Memo recorded with the split"),
+                )
+                .arg(
+                    Arg::with_name("split_id")
+                        .long("split-id")
+                        .value_name("TEXT")
+                        .takes_value(true)
+                        .help("Reference whose hash makes retrying the split safe"),
+                )
+                .arg(
+                    Arg::with_name("dry_run")
+                        .long("dry-run")
+                        .help("Simulate the split and print the amounts without sending it"),
+                ),
+        )
+}
+
+fn main() {
+    let matches = app().get_matches();
+    let result = Config::load(&matches).and_then(|config| match matches.subcommand() {
+        ("split", Some(matches)) => process_split(&config, matches),
+        _ => unreachable!(),
+    });
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        exit(1);
+    }
+}
+
+/// Payees, amount and policies given to the `split` command
+struct SplitArgs {
+    payees: Vec<Pubkey>,
+    amount: u64,
+    weights: Option<Weights>,
+    remainder: RemainderPolicy,
+    duplicates: DuplicatePolicy,
+    dust: DustPolicy,
+    memo: Option<String>,
+    /// Reference whose hash is the split id
+    reference: Option<String>,
+}
+
+impl SplitArgs {
+    /// Reads the `split` arguments, including payees from `--csv`
+    fn parse(matches: &ArgMatches) -> Result<Self, Box<dyn Error>> {
+        let mut payees: Vec<Payee> = match matches.values_of("payees") {
+            Some(values) => values.map(str::parse).collect::<Result<_, String>>()?,
+            None => vec![],
+        };
+        if let Some(path) = matches.value_of("csv") {
+            payees.extend(payees::read_csv(path)?);
+        }
+        if payees.is_empty() {
+            return Err("No payees given".into());
+        }
+
+        Ok(Self {
+            weights: payees::weights(&payees, matches.is_present("basis_points"))?,
+            payees: payees.iter().map(|payee| payee.pubkey).collect(),
+            amount: value_t_or_exit!(matches, "amount", u64),
+            remainder: match matches.value_of("remainder").unwrap() {
+                "first-payee" => RemainderPolicy::FirstPayee,
+                "last-payee" => RemainderPolicy::LastPayee,
+                "largest-remainder" => RemainderPolicy::LargestRemainder,
+                "reject-uneven" => RemainderPolicy::RejectUneven,
+                _ => RemainderPolicy::Payer,
+            },
+            duplicates: match matches.value_of("duplicates").unwrap() {
+                "merge" => DuplicatePolicy::Merge,
+                _ => DuplicatePolicy::Reject,
+            },
+            dust: match matches.value_of("dust").unwrap() {
+                "refund" => DustPolicy::Refund,
+                "redistribute" => DustPolicy::Redistribute,
+                "reject" => DustPolicy::Reject,
+                _ => DustPolicy::Allow,
+            },
+            memo: matches.value_of("memo").map(String::from),
+            reference: matches.value_of("split_id").map(String::from),
+        })
+    }
+
+    /// Split id recorded by the program, if a reference was given
+    fn split_id(&self) -> Option<[u8; 32]> {
+        self.reference
+            .as_ref()
+            .map(|reference| hash(reference.as_bytes()).to_bytes())
+    }
+
+    /// Builds a single split instruction paying every payee
+    fn instruction(&self, payer: &Pubkey, program_id: &Pubkey) -> Instruction {
+        let memo = match &self.memo {
+            Some(memo) => MemoPolicy::Inline(memo.clone()),
+            None => MemoPolicy::None,
+        };
+        match &self.weights {
+            Some(weights) => instruction::weighted_split(
+                program_id,
+                payer,
+                &self.payees,
+                self.amount,
+                weights.clone(),
+                self.remainder,
+                self.duplicates,
+                self.dust,
+                self.split_id(),
+                memo,
+            ),
+            None => instruction::split_with_options(
+                program_id,
+                payer,
+                &self.payees,
+                self.amount,
+                self.remainder,
+                self.duplicates,
+                self.dust,
+                self.split_id(),
+                memo,
+            ),
+        }
+    }
+}
+
+/// Connects to the cluster and reports what will be used
+fn connect(config: &Config) -> Result<RpcClient, Box<dyn Error>> {
+    let client =
+        RpcClient::new_with_commitment(config.json_rpc_url.clone(), CommitmentConfig::confirmed());
+    println!(
+        "Connection to cluster established: {} {}",
+        config.json_rpc_url,
+        client.get_version()?.solana_core
+    );
+    println!(
+        "Using account {} containing {} SOL to pay for the split",
+        config.payer.pubkey(),
+        lamports_to_sol(client.get_balance(&config.payer.pubkey())?)
+    );
+    println!("Using program {}", config.program_id);
+    Ok(client)
+}
+
+/// Simulates a transaction, printing its logs if it fails
+fn simulate(
+    client: &RpcClient,
+    transaction: &Transaction,
+) -> Result<Option<Vec<String>>, Box<dyn Error>> {
+    let result = client.simulate_transaction(transaction)?.value;
+    if let Some(err) = result.err {
+        for log in result.logs.unwrap_or_default() {
+            eprintln!("{}", log);
+        }
+        return Err(format!("Simulation failed: {}", err).into());
+    }
+    Ok(result.logs)
+}
+
+/// Sends a split, or simulates it with `--dry-run`, and prints what each
+/// payee was paid. Splits between more than `MAX_PAYEES` payees are sent
+/// in chunks.
+fn process_split(config: &Config, matches: &ArgMatches) -> Result<(), Box<dyn Error>> {
+    let args = SplitArgs::parse(matches)?;
+    let dry_run = matches.is_present("dry_run");
+    let client = connect(config)?;
+    if args.payees.len() > MAX_PAYEES {
+        return process_chunked_split(config, &client, args, dry_run);
+    }
+
+    let (recent_blockhash, _) = client.get_recent_blockhash()?;
+    let transaction = Transaction::new_signed_with_payer(
+        &[args.instruction(&config.payer.pubkey(), &config.program_id)],
+        Some(&config.payer.pubkey()),
+        &[&config.payer],
+        recent_blockhash,
+    );
+
+    let logs = if dry_run {
+        let logs = simulate(&client, &transaction)?;
+        println!("Simulated split, nothing was sent");
+        logs
+    } else {
+        let signature = client.send_and_confirm_transaction_with_spinner(&transaction)?;
+        println!("Signature: {}", signature);
+        client
+            .get_transaction_with_config(
+                &signature,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Json),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            )?
+            .transaction
+            .meta
+            .and_then(|meta| meta.log_messages)
+    };
+
+    for event in parse_logs(&config.program_id, &logs.unwrap_or_default()) {
+        print_event(&event);
+    }
+    Ok(())
+}
+
+/// Sends a split too large for one transaction as several, stopping at the
+/// first that fails. Every chunk records a split id, so rerunning with the
+/// same `--split-id` skips the chunks already paid.
+fn process_chunked_split(
+    config: &Config,
+    client: &RpcClient,
+    mut args: SplitArgs,
+    dry_run: bool,
+) -> Result<(), Box<dyn Error>> {
+    // Dust depends on payee balances, which change as chunks are paid, so a
+    // resumed split could be planned differently
+    if args.dust != DustPolicy::Allow {
+        return Err(format!(
+            "--dust only applies to splits between at most {} payees",
+            MAX_PAYEES
+        )
+        .into());
+    }
+    let reference = args
+        .reference
+        .get_or_insert_with(|| Keypair::new().pubkey().to_string())
+        .clone();
+    let payer = config.payer.pubkey();
+    let count = args.payees.len();
+    let chunked = chunk_split(
+        &config.program_id,
+        &payer,
+        &args.payees,
+        args.amount,
+        &args
+            .weights
+            .clone()
+            .unwrap_or_else(|| Weights::Shares(vec![1; count])),
+        args.remainder,
+        args.duplicates,
+        &vec![0; count],
+        DustPolicy::Allow,
+        args.split_id(),
+        args.memo.as_deref(),
+    )?;
+    let chunks = chunked.chunks.len();
+    println!(
+        "Splitting {} lamports between {} payees in {} transactions, split id {}",
+        chunked.total(),
+        count,
+        chunks,
+        reference
+    );
+
+    for (index, chunk) in chunked.chunks.iter().enumerate() {
+        let (record, _) =
+            SplitRecord::find_address(&config.program_id, &payer, &chunk.split_ids[0]);
+        if !dry_run
+            && client
+                .get_account_with_commitment(&record, CommitmentConfig::confirmed())?
+                .value
+                .is_some()
+        {
+            println!("Transaction {} of {} was already paid", index + 1, chunks);
+            continue;
+        }
+
+        let (recent_blockhash, _) = client.get_recent_blockhash()?;
+        let transaction = Transaction::new_signed_with_payer(
+            &chunk.instructions,
+            Some(&payer),
+            &[&config.payer],
+            recent_blockhash,
+        );
+        let result = if dry_run {
+            simulate(client, &transaction).map(|_| "simulated".to_string())
+        } else {
+            client
+                .send_and_confirm_transaction_with_spinner(&transaction)
+                .map(|signature| signature.to_string())
+                .map_err(Box::from)
+        };
+        match result {
+            Ok(outcome) => {
+                println!("Transaction {} of {}: {}", index + 1, chunks, outcome);
+                print_payees(&chunk.payees);
+            }
+            Err(err) => {
+                let unpaid = &chunked.chunks[index..];
+                eprintln!("Transaction {} of {} failed: {}", index + 1, chunks, err);
+                eprintln!(
+                    "{} lamports for {} payees were not paid:",
+                    unpaid.iter().map(Chunk::total).sum::<u64>(),
+                    unpaid.iter().map(|chunk| chunk.payees.len()).sum::<usize>()
+                );
+                for chunk in unpaid {
+                    print_payees(&chunk.payees);
+                }
+                eprintln!(
+                    "Rerun with the same payees, amount and options and --split-id {} to \
+                     resume, transactions already paid are skipped",
+                    reference
+                );
+                return Err(
+                    format!("Split stopped at transaction {} of {}", index + 1, chunks).into(),
+                );
+            }
+        }
+    }
+    if dry_run {
+        println!("Simulated split, nothing was sent");
+    }
+    println!(
+        "Remainder of {} lamports {}",
+        chunked.plan.distribution.remainder,
+        chunked.plan.distribution.policy.description()
+    );
+    Ok(())
+}
+
+/// Prints the amount for each payee
+fn print_payees(payees: &[PayeeAmount]) {
+    for payee in payees.iter() {
+        println!("{:<44} {:>20} lamports", payee.payee, payee.amount);
+    }
+}
+
+/// Prints the amount each payee received in a split
+fn print_event(event: &SplitEvent) {
+    print_payees(&event.payees);
+    println!(
+        "Remainder of {} lamports {}",
+        event.remainder,
+        event.policy.description()
+    );
+    if let Some(memo) = &event.memo {
+        println!("Memo: {}", memo);
+    }
+}
@@ -0,0 +1,180 @@
+//! Payees given on the command line or in a CSV file
+
+use helloworld::split::Weights;
+use solana_sdk::pubkey::Pubkey;
+use std::{convert::TryFrom, error::Error, fs, str::FromStr};
+
+/// A payee and its weight, if one was given
+#[derive(Clone, Debug, PartialEq)]
+pub struct Payee {
+    /// Account credited
+    pub pubkey: Pubkey,
+    /// Share of the split, or `None` for an even split
+    pub weight: Option<u64>,
+}
+
+impl Payee {
+    /// Parses a payee from its address and optional weight
+    fn parse(pubkey: &str, weight: Option<&str>) -> Result<Self, String> {
+        let pubkey = Pubkey::from_str(pubkey.trim())
+            .map_err(|_| format!("Invalid payee address '{}'", pubkey.trim()))?;
+        let weight = match weight.map(str::trim) {
+            Some(weight) => Some(
+                weight
+                    .parse()
+                    .map_err(|_| format!("Invalid weight '{}' for {}", weight, pubkey))?,
+            ),
+            None => None,
+        };
+        Ok(Self { pubkey, weight })
+    }
+}
+
+impl FromStr for Payee {
+    type Err = String;
+
+    /// Parses `PUBKEY` or `PUBKEY:WEIGHT`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut fields = s.splitn(2, ':');
+        Self::parse(fields.next().unwrap_or_default(), fields.next())
+    }
+}
+
+/// Reads payees from a CSV file with one `PUBKEY[,WEIGHT]` row per payee.
+/// Blank lines, `#` comments and a header row starting with `payee` are
+/// skipped.
+pub fn read_csv(path: &str) -> Result<Vec<Payee>, Box<dyn Error>> {
+    let contents =
+        fs::read_to_string(path).map_err(|err| format!("Failed to read '{}': {}", path, err))?;
+    let mut payees = vec![];
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if index == 0 && line.to_ascii_lowercase().starts_with("payee") {
+            continue;
+        }
+        let mut fields = line.splitn(2, ',');
+        let payee = Payee::parse(fields.next().unwrap_or_default(), fields.next())
+            .map_err(|err| format!("{} line {}: {}", path, index + 1, err))?;
+        payees.push(payee);
+    }
+    Ok(payees)
+}
+
+/// Weights for a weighted split, or `None` for an even split when no payee
+/// has a weight. Weights are read as basis points if `basis_points` is set.
+pub fn weights(payees: &[Payee], basis_points: bool) -> Result<Option<Weights>, String> {
+    let weights: Vec<u64> = payees.iter().filter_map(|payee| payee.weight).collect();
+    if weights.is_empty() && !basis_points {
+        return Ok(None);
+    }
+    if weights.len() != payees.len() {
+        return Err("Either every payee or no payee must have a weight".to_string());
+    }
+    if basis_points {
+        let basis_points = weights
+            .iter()
+            .map(|weight| {
+                u16::try_from(*weight).map_err(|_| format!("{} is too many basis points", weight))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Some(Weights::BasisPoints(basis_points)))
+    } else {
+        Ok(Some(Weights::Shares(weights)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    /// Reads payees from a CSV file holding `contents`, returning the file's
+    /// path with the result
+    fn read_temp_csv(name: &str, contents: &str) -> (String, Result<Vec<Payee>, String>) {
+        let path = env::temp_dir().join(format!("helloworld-cli-{}-{}.csv", process::id(), name));
+        let path = path.to_str().unwrap().to_string();
+        fs::write(&path, contents).unwrap();
+        let payees = read_csv(&path).map_err(|err| err.to_string());
+        fs::remove_file(&path).unwrap();
+        (path, payees)
+    }
+
+    #[test]
+    fn test_parse_payee() {
+        let pubkey = Pubkey::new_unique();
+        assert_eq!(
+            pubkey.to_string().parse::<Payee>().unwrap(),
+            Payee {
+                pubkey,
+                weight: None
+            }
+        );
+        assert_eq!(
+            format!("{}:250", pubkey).parse::<Payee>().unwrap(),
+            Payee {
+                pubkey,
+                weight: Some(250)
+            }
+        );
+        assert!(format!("{}:heavy", pubkey).parse::<Payee>().is_err());
+        assert!(format!("{}:", pubkey).parse::<Payee>().is_err());
+        assert!("not-a-pubkey:1".parse::<Payee>().is_err());
+    }
+
+    #[test]
+    fn test_read_csv() {
+        let (first, second) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (_, payees) = read_temp_csv(
+            "valid",
+            &format!("payee,weight\n# team\n{}, 3\n\n{}\n", first, second),
+        );
+        assert_eq!(
+            payees.unwrap(),
+            vec![
+                Payee {
+                    pubkey: first,
+                    weight: Some(3)
+                },
+                Payee {
+                    pubkey: second,
+                    weight: None
+                },
+            ]
+        );
+
+        // Errors name the file and line of the malformed row
+        let (path, payees) = read_temp_csv("malformed", &format!("{},1\n{};2\n", first, second));
+        let err = payees.unwrap_err();
+        assert!(err.contains(&format!("{} line 2", path)), "{}", err);
+        let (_, payees) = read_temp_csv("weight", &format!("{},-1\n", first));
+        assert!(payees.is_err());
+        assert!(read_csv("/nonexistent/payees.csv").is_err());
+    }
+
+    #[test]
+    fn test_weights() {
+        let payee = |weight| Payee {
+            pubkey: Pubkey::new_unique(),
+            weight,
+        };
+        assert_eq!(weights(&[payee(None), payee(None)], false).unwrap(), None);
+
+        let weighted = [payee(Some(7_500)), payee(Some(2_500))];
+        assert_eq!(
+            weights(&weighted, false).unwrap(),
+            Some(Weights::Shares(vec![7_500, 2_500]))
+        );
+        assert_eq!(
+            weights(&weighted, true).unwrap(),
+            Some(Weights::BasisPoints(vec![7_500, 2_500]))
+        );
+
+        // A missing weight can't be mixed with given ones
+        assert!(weights(&[payee(Some(1)), payee(None)], false).is_err());
+        assert!(weights(&[payee(None), payee(None)], true).is_err());
+        assert!(weights(&[payee(Some(70_000))], true).is_err());
+    }
+}
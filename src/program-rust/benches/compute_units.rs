@@ -0,0 +1,148 @@
+//! Measures the compute units a split uses for each payee count, both as the
+//! original client's 8-byte legacy payload and as a tagged `Split`.
+//!
+//! Runs against the BPF build of the program, so build it first:
+//!
+//! ```text
+//! cargo build-bpf --bpf-out-dir=../../dist/program
+//! BPF_OUT_DIR=../../dist/program cargo bench --bench compute_units
+//! ```
+//!
+//! Every version of the program accepts the legacy payload, so `BPF_OUT_DIR`
+//! may point at an older build to compare against. Versions without tagged
+//! instructions show `-` in the `Split` column.
+
+use helloworld::{
+    instruction,
+    split::{DuplicatePolicy, DustPolicy, MemoPolicy, RemainderPolicy, MAX_PAYEES},
+};
+use solana_program_test::{tokio::runtime, ProgramTest};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::Signer,
+    system_program,
+    transaction::Transaction,
+};
+
+/// Compute units a transaction may use unless it asks for more
+const DEFAULT_COMPUTE_UNITS: u64 = 200_000;
+
+/// Upper bound of the search, well past what any payee count needs
+const MAX_COMPUTE_UNITS: u64 = 1_400_000;
+
+/// Lamports split to each payee
+const LAMPORTS_PER_PAYEE: u64 = 1_000_000;
+
+/// Split payloads the benchmark measures
+#[derive(Clone, Copy)]
+enum Payload {
+    /// The original client's 8 little-endian amount bytes
+    Legacy,
+    /// A tagged `Split` built by `instruction::split_with_options`
+    Tagged,
+}
+
+/// Split of `amount` from `payer` between `payees`, encoded as `payload`
+fn split_instruction(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    payees: &[Pubkey],
+    amount: u64,
+    payload: Payload,
+) -> Instruction {
+    match payload {
+        Payload::Legacy => {
+            let mut accounts = vec![
+                AccountMeta::new(*payer, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ];
+            accounts.extend(payees.iter().map(|payee| AccountMeta::new(*payee, false)));
+            Instruction::new_with_bytes(*program_id, &amount.to_le_bytes(), accounts)
+        }
+        Payload::Tagged => instruction::split_with_options(
+            program_id,
+            payer,
+            payees,
+            amount,
+            RemainderPolicy::FirstPayee,
+            DuplicatePolicy::Reject,
+            DustPolicy::Allow,
+            None,
+            MemoPolicy::None,
+        ),
+    }
+}
+
+/// Whether a split between `payees` succeeds with `max_units` compute units
+async fn split_succeeds(
+    program_id: Pubkey,
+    payees: &[Pubkey],
+    payload: Payload,
+    max_units: u64,
+) -> bool {
+    let mut program_test = ProgramTest::new("helloworld", program_id, None);
+    program_test.prefer_bpf(true);
+    program_test.set_bpf_compute_max_units(max_units);
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[split_instruction(
+            &program_id,
+            &payer.pubkey(),
+            payees,
+            LAMPORTS_PER_PAYEE * payees.len() as u64,
+            payload,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.is_ok()
+}
+
+/// Fewest compute units a split between `payees` succeeds with
+async fn compute_units(program_id: Pubkey, payees: &[Pubkey], payload: Payload) -> Option<u64> {
+    if !split_succeeds(program_id, payees, payload, MAX_COMPUTE_UNITS).await {
+        return None;
+    }
+    let (mut low, mut high) = (0, MAX_COMPUTE_UNITS);
+    while high - low > 1 {
+        let middle = low + (high - low) / 2;
+        if split_succeeds(program_id, payees, payload, middle).await {
+            high = middle;
+        } else {
+            low = middle;
+        }
+    }
+    Some(high)
+}
+
+/// Formats a measurement, marking a split that fit in the default budget
+fn format_units(units: Option<u64>) -> String {
+    match units {
+        Some(units) if units <= DEFAULT_COMPUTE_UNITS => units.to_string(),
+        Some(units) => format!("{} (over)", units),
+        None => "-".to_string(),
+    }
+}
+
+fn main() {
+    let runtime = runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .unwrap();
+    let program_id = Pubkey::new_unique();
+
+    println!("{:>6}  {:>14}  {:>14}", "payees", "Legacy", "Split");
+    for count in 1..=MAX_PAYEES {
+        let payees: Vec<Pubkey> = (0..count).map(|_| Pubkey::new_unique()).collect();
+        let legacy = runtime.block_on(compute_units(program_id, &payees, Payload::Legacy));
+        let tagged = runtime.block_on(compute_units(program_id, &payees, Payload::Tagged));
+        println!(
+            "{:>6}  {:>14}  {:>14}",
+            count,
+            format_units(legacy),
+            format_units(tagged)
+        );
+    }
+}
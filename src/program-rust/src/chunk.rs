@@ -0,0 +1,225 @@
+//! Client-side splitting of distributions too large for one transaction
+
+use crate::{
+    event::PayeeAmount,
+    instruction,
+    split::{
+        self, DuplicatePolicy, DustPolicy, MemoPolicy, Plan, RemainderPolicy, Weights, MAX_PAYEES,
+    },
+};
+use solana_program::{
+    hash::hashv, instruction::Instruction, message::Message, program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Largest serialized transaction the cluster accepts, `PACKET_DATA_SIZE`
+/// in the Solana SDK
+pub const MAX_TRANSACTION_SIZE: usize = 1232;
+
+/// Size of the payer's signature and the signature count before it
+const SIGNATURE_SIZE: usize = 1 + 64;
+
+/// Split instructions sent together in one transaction
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chunk {
+    /// Payees the chunk pays, in payee order
+    pub payees: Vec<PayeeAmount>,
+    /// Split id of each instruction, if the distribution has one
+    pub split_ids: Vec<[u8; 32]>,
+    /// Instructions for the chunk's transaction, signed by the payer
+    pub instructions: Vec<Instruction>,
+}
+
+impl Chunk {
+    /// Lamports the chunk debits from the payer
+    pub fn total(&self) -> u64 {
+        self.payees.iter().map(|payee| payee.amount).sum()
+    }
+}
+
+/// A distribution planned as a whole and cut into chunks
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChunkedSplit {
+    /// Payments worked out for every payee together, so the remainder and
+    /// dust are handled once for the whole distribution
+    pub plan: Plan,
+    /// Transactions that together pay the plan
+    pub chunks: Vec<Chunk>,
+}
+
+impl ChunkedSplit {
+    /// Lamports the chunks debit from the payer altogether
+    pub fn total(&self) -> u64 {
+        self.plan.distribution.total()
+    }
+}
+
+/// Split id of the instruction at `index` in a chunked distribution with
+/// the id `split_id`
+pub fn chunk_split_id(split_id: &[u8; 32], index: usize) -> [u8; 32] {
+    hashv(&[split_id, &(index as u64).to_le_bytes()]).to_bytes()
+}
+
+/// Plans a split of `amount` between any number of payees like
+/// `plan_distribution`, then cuts it into transactions of split
+/// instructions, each paying at most `MAX_PAYEES` payees.
+///
+/// Every instruction pays exactly its payees' planned amounts and fails
+/// otherwise, so the payees receive what one split would have paid them.
+/// With a `split_id` each instruction records its own id from
+/// `chunk_split_id`, so resending chunks after a partial failure can't pay
+/// anyone twice. The plan depends on `shortfalls`, so a resumed distribution
+/// must be planned with the shortfalls it was first planned with.
+#[allow(clippy::too_many_arguments)]
+pub fn chunk_split(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    payees: &[Pubkey],
+    amount: u64,
+    weights: &Weights,
+    remainder: RemainderPolicy,
+    duplicates: DuplicatePolicy,
+    shortfalls: &[u64],
+    dust: DustPolicy,
+    split_id: Option<[u8; 32]>,
+    memo: Option<&str>,
+) -> Result<ChunkedSplit, ProgramError> {
+    let plan = split::plan_distribution(
+        payer, payees, amount, weights, remainder, duplicates, shortfalls, dust,
+    )?;
+    if let Some(memo) = memo {
+        split::check_memo(memo)?;
+    }
+
+    // Payees left with nothing are not paid at all
+    let paid: Vec<PayeeAmount> = plan
+        .indices
+        .iter()
+        .zip(&plan.distribution.amounts)
+        .filter(|(_, amount)| **amount > 0)
+        .map(|(index, amount)| PayeeAmount {
+            payee: payees[*index],
+            amount: *amount,
+        })
+        .collect();
+
+    let mut chunks: Vec<Chunk> = vec![];
+    for (index, group) in paid.chunks(MAX_PAYEES).enumerate() {
+        let split_id = split_id.map(|split_id| chunk_split_id(&split_id, index));
+        let group_payees: Vec<Pubkey> = group.iter().map(|payee| payee.payee).collect();
+        let amounts: Vec<u64> = group.iter().map(|payee| payee.amount).collect();
+        // Weighting each payee by its own amount pays it exactly
+        let instruction = instruction::weighted_split(
+            program_id,
+            payer,
+            &group_payees,
+            amounts.iter().sum(),
+            Weights::Shares(amounts),
+            RemainderPolicy::RejectUneven,
+            DuplicatePolicy::Reject,
+            DustPolicy::Allow,
+            split_id,
+            memo.map_or(MemoPolicy::None, |memo| {
+                MemoPolicy::Inline(memo.to_string())
+            }),
+        );
+
+        let fits = match chunks.last() {
+            Some(chunk) => {
+                let mut instructions = chunk.instructions.clone();
+                instructions.push(instruction.clone());
+                transaction_size(payer, &instructions) <= MAX_TRANSACTION_SIZE
+            }
+            None => false,
+        };
+        if !fits {
+            chunks.push(Chunk {
+                payees: vec![],
+                split_ids: vec![],
+                instructions: vec![],
+            });
+        }
+        let chunk = chunks.last_mut().unwrap();
+        chunk.payees.extend_from_slice(group);
+        chunk.split_ids.extend(split_id);
+        chunk.instructions.push(instruction);
+    }
+    Ok(ChunkedSplit { plan, chunks })
+}
+
+/// Size of a transaction carrying `instructions`, signed only by `payer`
+pub fn transaction_size(payer: &Pubkey, instructions: &[Instruction]) -> usize {
+    SIGNATURE_SIZE + Message::new(instructions, Some(payer)).serialize().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::SplitError, split::MAX_MEMO_LEN};
+
+    #[test]
+    fn test_chunk_split_id() {
+        let split_id = [7; 32];
+        assert_eq!(chunk_split_id(&split_id, 1), chunk_split_id(&split_id, 1));
+        assert_ne!(chunk_split_id(&split_id, 0), chunk_split_id(&split_id, 1));
+        assert_ne!(chunk_split_id(&split_id, 0), chunk_split_id(&[8; 32], 0));
+    }
+
+    #[test]
+    fn test_chunk_split_skips_unpaid_payees() {
+        let program_id = Pubkey::new_unique();
+        let payer = Pubkey::new_unique();
+        let payees: Vec<Pubkey> = (0..12).map(|_| Pubkey::new_unique()).collect();
+        // The first payee is weighted zero and the second's share is refunded
+        let mut shares = vec![1; 12];
+        shares[0] = 0;
+        let mut shortfalls = vec![0; 12];
+        shortfalls[1] = 1_000;
+
+        let chunked = chunk_split(
+            &program_id,
+            &payer,
+            &payees,
+            1_100,
+            &Weights::Shares(shares),
+            RemainderPolicy::Payer,
+            DuplicatePolicy::Reject,
+            &shortfalls,
+            DustPolicy::Refund,
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(chunked.total(), 1_000);
+        assert_eq!(chunked.chunks.len(), 1);
+        let chunk = &chunked.chunks[0];
+        assert_eq!(chunk.payees.len(), 10);
+        assert_eq!(chunk.payees[0].payee, payees[2]);
+        assert_eq!(chunk.total(), 1_000);
+        assert_eq!(chunk.instructions.len(), 1);
+        assert!(chunk.split_ids.is_empty());
+    }
+
+    #[test]
+    fn test_chunk_split_rejects_long_memo() {
+        let payees = [Pubkey::new_unique()];
+        let memo = "x".repeat(MAX_MEMO_LEN + 1);
+        assert_eq!(
+            chunk_split(
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &payees,
+                100,
+                &Weights::Shares(vec![1]),
+                RemainderPolicy::Payer,
+                DuplicatePolicy::Reject,
+                &[0],
+                DustPolicy::Allow,
+                None,
+                Some(&memo),
+            )
+            .unwrap_err(),
+            SplitError::InvalidMemo.into()
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! Error types
+
+use num_derive::FromPrimitive;
+use num_traits::FromPrimitive;
+use solana_program::{
+    decode_error::DecodeError,
+    msg,
+    program_error::{PrintProgramError, ProgramError},
+};
+use thiserror::Error;
+
+/// Errors that may be returned by the split program.
+///
+/// Each variant maps to `ProgramError::Custom` with the code given here. Codes
+/// start at 0x100 so they can't be mistaken for system program errors raised
+/// during a transfer CPI, and must never be renumbered.
+#[derive(Clone, Debug, Eq, Error, FromPrimitive, PartialEq)]
+pub enum SplitError {
+    /// The instruction data could not be decoded
+    #[error("Invalid instruction")]
+    InvalidInstruction = 0x100,
+    /// The instruction version byte is not supported
+    #[error("Unsupported instruction version")]
+    UnsupportedVersion = 0x101,
+    /// No payees were given, or more than the program can pay at once
+    #[error("Invalid number of payees")]
+    InvalidPayeeCount = 0x102,
+    /// The system program was not passed where it was expected
+    #[error("Missing system program")]
+    MissingSystemProgram = 0x103,
+    /// The number of weights doesn't match the number of payees
+    #[error("Weight count doesn't match payee count")]
+    WeightCountMismatch = 0x104,
+    /// Basis point weights don't add up to 10,000
+    #[error("Basis points don't add up to 10,000")]
+    InvalidBasisPoints = 0x105,
+    /// Every weight is zero
+    #[error("Weights add up to zero")]
+    ZeroWeights = 0x106,
+    /// The amount doesn't divide evenly and the remainder policy rejects it
+    #[error("Amount doesn't divide evenly")]
+    UnevenSplit = 0x107,
+    /// An amount overflowed
+    #[error("Arithmetic overflow")]
+    Overflow = 0x108,
+    /// An account's address doesn't match the program address derived for it
+    #[error("Invalid account address")]
+    InvalidAccountAddress = 0x109,
+    /// A program-owned account holds a different kind of state than expected
+    #[error("Unexpected account kind")]
+    UnexpectedAccountKind = 0x10a,
+    /// The signer is not the authority recorded in the account
+    #[error("Invalid authority")]
+    InvalidAuthority = 0x10b,
+    /// The payee accounts don't match the stored recipients
+    #[error("Payee accounts don't match the split group")]
+    PayeeMismatch = 0x10c,
+    /// The page was already paid, or pages were requested out of order
+    #[error("Stale distribution cursor")]
+    StaleCursor = 0x10d,
+    /// The distribution is not in a state that allows the instruction
+    #[error("Invalid distribution status")]
+    InvalidDistributionStatus = 0x10e,
+    /// The account can't hold any more recipients
+    #[error("Recipient capacity exceeded")]
+    CapacityExceeded = 0x10f,
+    /// The payee has no lamports waiting to be withdrawn
+    #[error("Nothing to withdraw")]
+    NothingToWithdraw = 0x110,
+    /// Payees still have lamports waiting to be withdrawn
+    #[error("Entitlements outstanding")]
+    EntitlementsOutstanding = 0x111,
+    /// The SPL Token program was not passed where it was expected
+    #[error("Missing token program")]
+    MissingTokenProgram = 0x112,
+    /// A payee is listed more than once and duplicates are rejected
+    #[error("Duplicate payee")]
+    DuplicatePayee = 0x113,
+    /// The payer, or the source token account, is also listed as a payee
+    #[error("Payer listed as payee")]
+    PayerIsPayee = 0x114,
+    /// The system program is listed as a payee
+    #[error("System program listed as payee")]
+    SystemProgramIsPayee = 0x115,
+    /// A payee's share would leave it below the rent-exempt minimum
+    #[error("Payee share below rent-exempt minimum")]
+    DustShare = 0x116,
+    /// A subscription interval or multisig period has zero length
+    #[error("Invalid interval")]
+    InvalidInterval = 0x117,
+    /// A subscription payment was cranked before it was due
+    #[error("Subscription payment not due")]
+    PaymentNotDue = 0x118,
+    /// A subscription has a payment due that must be cranked first
+    #[error("Subscription payment due")]
+    PaymentDue = 0x119,
+    /// A vesting cliff or duration is out of range
+    #[error("Invalid vesting schedule")]
+    InvalidSchedule = 0x11a,
+    /// A vesting schedule was already revoked
+    #[error("Vesting schedule revoked")]
+    VestingRevoked = 0x11b,
+    /// A multisig's signers or threshold are invalid
+    #[error("Invalid multisig threshold")]
+    InvalidThreshold = 0x11c,
+    /// Fewer multisig signers signed than the split requires
+    #[error("Not enough multisig signers")]
+    NotEnoughSigners = 0x11d,
+    /// A key that isn't one of the multisig's signers tried to approve
+    #[error("Not a multisig signer")]
+    NotMultisigSigner = 0x11e,
+    /// A multisig signer approved the same proposal twice
+    #[error("Proposal already approved by signer")]
+    AlreadyApproved = 0x11f,
+    /// A proposal was already executed
+    #[error("Proposal already executed")]
+    ProposalExecuted = 0x120,
+    /// A logged event could not be decoded
+    #[error("Invalid event")]
+    InvalidEvent = 0x121,
+    /// A logged event has a version this crate doesn't know
+    #[error("Unsupported event version")]
+    UnsupportedEventVersion = 0x122,
+    /// A receipt doesn't hash to the next receipt's previous hash
+    #[error("Broken receipt chain")]
+    BrokenReceiptChain = 0x123,
+    /// A split with the same split id was already paid
+    #[error("Split id already used")]
+    SplitIdUsed = 0x124,
+    /// A split that requires a memo has none
+    #[error("Memo required")]
+    MissingMemo = 0x125,
+    /// A memo is empty, too long or not UTF-8
+    #[error("Invalid memo")]
+    InvalidMemo = 0x126,
+    /// A split id was given for a payer owned by this program, which can't
+    /// fund the split record through the system program
+    #[error("Program-owned payer can't record a split id")]
+    ProgramOwnedPayerSplitId = 0x127,
+}
+
+impl SplitError {
+    /// Decodes a `ProgramError::Custom` code, e.g. from a failed transaction,
+    /// into the error that caused it
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::from_u32(code)
+    }
+
+    /// Decodes a program error into a split error, if it is one
+    pub fn from_program_error(error: &ProgramError) -> Option<Self> {
+        match error {
+            ProgramError::Custom(code) => Self::from_code(*code),
+            _ => None,
+        }
+    }
+}
+
+impl From<SplitError> for ProgramError {
+    fn from(e: SplitError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+impl<T> DecodeError<T> for SplitError {
+    fn type_of() -> &'static str {
+        "SplitError"
+    }
+}
+
+impl PrintProgramError for SplitError {
+    fn print<E>(&self)
+    where
+        E: 'static + std::error::Error + DecodeError<E> + PrintProgramError + FromPrimitive,
+    {
+        msg!("Error: {}", self);
+    }
+}
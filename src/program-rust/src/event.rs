@@ -0,0 +1,379 @@
+//! Machine-parseable records of completed splits
+
+use crate::{
+    error::SplitError,
+    split::{Distribution, PayeeKey, Plan, RemainderPolicy},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    hash::{Hash, Hasher},
+    log::sol_log,
+    pubkey::Pubkey,
+};
+
+/// Version byte that prefixes every encoded event
+pub const EVENT_VERSION: u8 = 3;
+
+/// Version of events logged before `SplitEvent::memo` was added
+const EVENT_VERSION_1: u8 = 1;
+
+/// Version of events logged before `SplitEvent::split_id` was added
+const EVENT_VERSION_2: u8 = 2;
+
+/// Marks a log message as a base64 encoded event
+pub const EVENT_LOG_PREFIX: &str = "split-event:";
+
+/// Marks a log message as a base64 encoded quote
+pub const QUOTE_LOG_PREFIX: &str = "split-quote:";
+
+/// Prefix the runtime adds to messages logged by a program
+const PROGRAM_LOG_PREFIX: &str = "Program log: ";
+
+/// Amount paid to one payee
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct PayeeAmount {
+    /// Account credited
+    pub payee: Pubkey,
+    /// Lamports, or tokens for a token split, credited
+    pub amount: u64,
+}
+
+/// Record of a completed split, logged as `split-event:<base64>` where the
+/// decoded bytes are the version followed by the Borsh encoded event
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct SplitEvent {
+    /// Hash of the slot, payer, split id and payee amounts, identifying the
+    /// split. Splits without a split id paying the same amounts from the
+    /// same payer in one slot share an id.
+    pub id: Hash,
+    /// Account the split was paid from
+    pub payer: Pubkey,
+    /// Mint of the tokens split, or `None` for lamports
+    pub mint: Option<Pubkey>,
+    /// Amount paid to each payee, in payee order
+    pub payees: Vec<PayeeAmount>,
+    /// Left over after rounding every amount down
+    pub remainder: u64,
+    /// Policy that decided where `remainder` went
+    pub policy: RemainderPolicy,
+    /// Reference the split carried, such as an invoice number
+    pub memo: Option<String>,
+    /// Id the split was recorded under, if the caller gave one
+    pub split_id: Option<[u8; 32]>,
+}
+
+impl SplitEvent {
+    /// Builds the event for `distribution` paid to `payees` in `slot`, by
+    /// the split recorded as `split_id` if it had one
+    pub fn new<P: PayeeKey>(
+        slot: u64,
+        payer: &Pubkey,
+        mint: Option<&Pubkey>,
+        payees: &[P],
+        distribution: &Distribution,
+        split_id: Option<&[u8; 32]>,
+        memo: Option<&str>,
+    ) -> Self {
+        let mut hasher = Hasher::default();
+        hasher.hashv(&[&slot.to_le_bytes(), payer.as_ref()]);
+        if let Some(split_id) = split_id {
+            hasher.hash(split_id);
+        }
+        let payees: Vec<PayeeAmount> = payees
+            .iter()
+            .zip(&distribution.amounts)
+            .map(|(payee, amount)| {
+                let payee = payee.payee_key();
+                hasher.hashv(&[payee.as_ref(), &amount.to_le_bytes()]);
+                PayeeAmount {
+                    payee: *payee,
+                    amount: *amount,
+                }
+            })
+            .collect();
+        Self {
+            id: hasher.result(),
+            payer: *payer,
+            mint: mint.copied(),
+            payees,
+            remainder: distribution.remainder,
+            policy: distribution.policy,
+            memo: memo.map(String::from),
+            split_id: split_id.copied(),
+        }
+    }
+
+    /// Encodes the event, prefixed with its version
+    pub fn pack(&self) -> Vec<u8> {
+        pack(self)
+    }
+
+    /// Decodes an event encoded with `pack`. Older events decode with no
+    /// memo or split id where they predate the field.
+    pub fn unpack(input: &[u8]) -> Result<Self, SplitError> {
+        // Borsh encodes a missing memo or split id as a single zero byte
+        unpack(input, &[&[0], &[0]])
+    }
+
+    /// Formats the event as the message `log` writes
+    pub fn to_log(&self) -> String {
+        to_log(EVENT_LOG_PREFIX, &self.pack())
+    }
+
+    /// Writes the event to the program log
+    pub fn log(&self) {
+        sol_log(&self.to_log());
+    }
+
+    /// Decodes the event in a log message, with or without the runtime's
+    /// `Program log: ` prefix. Returns `None` for messages that aren't events.
+    pub fn from_log(message: &str) -> Option<Result<Self, SplitError>> {
+        from_log(message, EVENT_LOG_PREFIX, Self::unpack)
+    }
+}
+
+/// Payments a split would make, logged by `Quote` as `split-quote:<base64>`
+/// in the same encoding as `SplitEvent`
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct QuoteEvent {
+    /// Account the split would be paid from
+    pub payer: Pubkey,
+    /// Amount each payee would be paid, in payee order after duplicates
+    /// are handled
+    pub payees: Vec<PayeeAmount>,
+    /// Left over after rounding every amount down
+    pub remainder: u64,
+    /// Policy that decides where `remainder` goes
+    pub policy: RemainderPolicy,
+    /// Shares below their payee's rent-exempt minimum, before the dust
+    /// policy was applied
+    pub dust: Vec<PayeeAmount>,
+}
+
+impl QuoteEvent {
+    /// Builds the quote for `plan`, whose indices refer to `payees`
+    pub fn new<P: PayeeKey>(payer: &Pubkey, payees: &[P], plan: &Plan) -> Self {
+        Self {
+            payer: *payer,
+            payees: plan
+                .indices
+                .iter()
+                .zip(&plan.distribution.amounts)
+                .map(|(index, amount)| PayeeAmount {
+                    payee: *payees[*index].payee_key(),
+                    amount: *amount,
+                })
+                .collect(),
+            remainder: plan.distribution.remainder,
+            policy: plan.distribution.policy,
+            dust: plan
+                .dust
+                .iter()
+                .map(|share| PayeeAmount {
+                    payee: *payees[share.index].payee_key(),
+                    amount: share.amount,
+                })
+                .collect(),
+        }
+    }
+
+    /// Lamports the split would debit from the payer
+    pub fn total(&self) -> u64 {
+        self.payees.iter().map(|payee| payee.amount).sum()
+    }
+
+    /// Encodes the quote, prefixed with its version
+    pub fn pack(&self) -> Vec<u8> {
+        pack(self)
+    }
+
+    /// Decodes a quote encoded with `pack`
+    pub fn unpack(input: &[u8]) -> Result<Self, SplitError> {
+        unpack(input, &[&[], &[]])
+    }
+
+    /// Formats the quote as the message `log` writes
+    pub fn to_log(&self) -> String {
+        to_log(QUOTE_LOG_PREFIX, &self.pack())
+    }
+
+    /// Writes the quote to the program log
+    pub fn log(&self) {
+        sol_log(&self.to_log());
+    }
+
+    /// Decodes the quote in a log message, with or without the runtime's
+    /// `Program log: ` prefix. Returns `None` for messages that aren't quotes.
+    pub fn from_log(message: &str) -> Option<Result<Self, SplitError>> {
+        from_log(message, QUOTE_LOG_PREFIX, Self::unpack)
+    }
+}
+
+/// Encodes `value` prefixed with `EVENT_VERSION`
+fn pack<T: BorshSerialize>(value: &T) -> Vec<u8> {
+    let mut buf = vec![EVENT_VERSION];
+    // Writing to a `Vec` can't fail
+    value.serialize(&mut buf).unwrap();
+    buf
+}
+
+/// Formats packed bytes as a log message, base64 encoding them straight
+/// after `prefix`
+fn to_log(prefix: &str, packed: &[u8]) -> String {
+    let mut log = String::with_capacity(prefix.len() + packed.len() * 4 / 3 + 4);
+    log.push_str(prefix);
+    base64::encode_config_buf(packed, base64::STANDARD, &mut log);
+    log
+}
+
+/// Decodes a value encoded with `pack`. Older values are decoded after
+/// appending the encoding of the fields added since: `added[0]` holds the
+/// fields added in version 2, `added[1]` those added in version 3.
+fn unpack<T: BorshDeserialize>(input: &[u8], added: &[&[u8]; 2]) -> Result<T, SplitError> {
+    let (version, rest) = input.split_first().ok_or(SplitError::InvalidEvent)?;
+    let data = match *version {
+        EVENT_VERSION => rest.to_vec(),
+        EVENT_VERSION_2 => [rest, added[1]].concat(),
+        EVENT_VERSION_1 => [rest, added[0], added[1]].concat(),
+        _ => return Err(SplitError::UnsupportedEventVersion),
+    };
+    T::try_from_slice(&data).map_err(|_| SplitError::InvalidEvent)
+}
+
+/// Decodes the value logged after `prefix` in a log message with `unpack`
+fn from_log<T>(
+    message: &str,
+    prefix: &str,
+    unpack: fn(&[u8]) -> Result<T, SplitError>,
+) -> Option<Result<T, SplitError>> {
+    let message = message.strip_prefix(PROGRAM_LOG_PREFIX).unwrap_or(message);
+    let encoded = message.strip_prefix(prefix)?;
+    Some(
+        base64::decode(encoded)
+            .map_err(|_| SplitError::InvalidEvent)
+            .and_then(|data| unpack(&data)),
+    )
+}
+
+/// Picks out the messages logged by `program_id` itself from a
+/// transaction's log messages, following the runtime's `Program <id> invoke`
+/// and `Program <id> success` or `failed` messages. Messages logged by
+/// programs it invokes, or by any other program, are left out.
+fn program_messages<'a, S: AsRef<str>>(
+    program_id: &Pubkey,
+    messages: &'a [S],
+) -> impl Iterator<Item = &'a str> {
+    let program_id = program_id.to_string();
+    let mut invoked: Vec<&'a str> = vec![];
+    messages.iter().filter_map(move |message| {
+        let message = message.as_ref();
+        if message.starts_with(PROGRAM_LOG_PREFIX) {
+            return match invoked.last() {
+                Some(id) if *id == program_id => Some(message),
+                _ => None,
+            };
+        }
+        let mut words = message.split(' ');
+        if let (Some("Program"), Some(id), Some(status)) =
+            (words.next(), words.next(), words.next())
+        {
+            match status {
+                "invoke" => invoked.push(id),
+                "success" | "failed:" => {
+                    invoked.pop();
+                }
+                _ => {}
+            }
+        }
+        None
+    })
+}
+
+/// Rebuilds every split event `program_id` logged in a transaction's log
+/// messages, in order. Messages that don't decode are skipped.
+pub fn parse_logs<S: AsRef<str>>(program_id: &Pubkey, messages: &[S]) -> Vec<SplitEvent> {
+    program_messages(program_id, messages)
+        .filter_map(|message| SplitEvent::from_log(message)?.ok())
+        .collect()
+}
+
+/// Rebuilds every quote `program_id` logged in a transaction's log messages,
+/// in order. Messages that don't decode are skipped.
+pub fn parse_quote_logs<S: AsRef<str>>(program_id: &Pubkey, messages: &[S]) -> Vec<QuoteEvent> {
+    program_messages(program_id, messages)
+        .filter_map(|message| QuoteEvent::from_log(message)?.ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(memo: Option<&str>) -> SplitEvent {
+        SplitEvent {
+            id: Hash::new_unique(),
+            payer: Pubkey::new_unique(),
+            mint: Some(Pubkey::new_unique()),
+            payees: vec![PayeeAmount {
+                payee: Pubkey::new_unique(),
+                amount: 500,
+            }],
+            remainder: 1,
+            policy: RemainderPolicy::LastPayee,
+            memo: memo.map(String::from),
+            split_id: None,
+        }
+    }
+
+    #[test]
+    fn test_pack_unpack() {
+        let event = SplitEvent {
+            split_id: Some([7; 32]),
+            ..event(Some("invoice 42"))
+        };
+        let data = event.pack();
+        assert_eq!(data[0], EVENT_VERSION);
+        assert_eq!(SplitEvent::unpack(&data).unwrap(), event);
+
+        let quote = QuoteEvent {
+            payer: event.payer,
+            payees: event.payees.clone(),
+            remainder: 1,
+            policy: RemainderPolicy::Payer,
+            dust: vec![],
+        };
+        assert_eq!(QuoteEvent::unpack(&quote.pack()).unwrap(), quote);
+    }
+
+    #[test]
+    fn test_unpack_older_versions() {
+        let event = event(None);
+        // Version 2 events end before the split id, version 1 events before
+        // the memo
+        let mut data = event.pack();
+        data[0] = EVENT_VERSION_2;
+        data.pop();
+        assert_eq!(SplitEvent::unpack(&data).unwrap(), event);
+        data[0] = EVENT_VERSION_1;
+        data.pop();
+        assert_eq!(SplitEvent::unpack(&data).unwrap(), event);
+    }
+
+    #[test]
+    fn test_unpack_invalid() {
+        let data = event(None).pack();
+        assert_eq!(SplitEvent::unpack(&[]), Err(SplitError::InvalidEvent));
+        assert_eq!(
+            SplitEvent::unpack(&data[..data.len() - 1]),
+            Err(SplitError::InvalidEvent)
+        );
+        assert_eq!(
+            SplitEvent::unpack(&[&data[..], &[0]].concat()),
+            Err(SplitError::InvalidEvent)
+        );
+        assert_eq!(
+            SplitEvent::unpack(&[&[EVENT_VERSION + 1], &data[1..]].concat()),
+            Err(SplitError::UnsupportedEventVersion)
+        );
+    }
+}
@@ -0,0 +1,1558 @@
+//! Instruction types
+
+use crate::{
+    error::SplitError,
+    split::{DuplicatePolicy, DustPolicy, MemoPolicy, RemainderPolicy, Weights},
+    state::{
+        find_vault_address, Escrow, Interval, Multisig, PagedDistribution, PayeeLedger, Proposal,
+        ReceiptLog, SplitGroup, SplitRecord, Subscription, Vesting,
+    },
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_program, sysvar,
+};
+use std::convert::TryInto;
+
+/// Version byte that prefixes every tagged instruction
+pub const INSTRUCTION_VERSION: u8 = 1;
+
+/// Length of the raw little-endian amount sent by legacy clients. Tagged
+/// instructions are never exactly this long, so the two encodings can't be
+/// confused. Legacy clients may send trailing bytes, which are ignored.
+pub const LEGACY_INSTRUCTION_LEN: usize = 8;
+
+/// Instructions supported by the split program
+///
+/// Tagged instructions are encoded as `[INSTRUCTION_VERSION, borsh(SplitInstruction)]`.
+/// A payload of exactly `LEGACY_INSTRUCTION_LEN` bytes is decoded as `Legacy`,
+/// as is any longer payload that doesn't start with `INSTRUCTION_VERSION`,
+/// from its first `LEGACY_INSTRUCTION_LEN` bytes. A legacy payload with
+/// trailing bytes whose amount has a low byte of `INSTRUCTION_VERSION` is
+/// read as tagged.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub enum SplitInstruction {
+    /// Split `amount` lamports evenly between the payees, decoded from the
+    /// untagged 8-byte payload used by the original client. As in the
+    /// original program, every payee listed is paid, even a repeated payee
+    /// or the payer itself, and any remainder stays with the payer.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Debit lamports
+    /// 1. `[]`                 System program
+    /// 2. ..2+N `[writable]`   Payees, each credited amount/N
+    ///
+    /// The payer's receipt log may follow the payees to record the split.
+    Legacy {
+        /// Total lamports to split
+        amount: u64,
+    },
+
+    /// Split `amount` lamports evenly between the payees, handing out any
+    /// remainder according to `remainder`. A split given a `split_id` is
+    /// recorded at the program address of `[b"split_id", payer, split_id]`,
+    /// and fails with `SplitIdUsed` if that id was already paid. The memo
+    /// required by `memo` is included in the split event. A payer owned by
+    /// this program is debited directly rather than through the system
+    /// program, and must stay rent exempt. Such a payer can't pay for a
+    /// split record, so giving it a `split_id` fails with
+    /// `ProgramOwnedPayerSplitId`.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Debit lamports
+    /// 1. `[]`                 System program
+    /// 2. ..2+N `[writable]`   Payees, each credited amount/N
+    ///
+    /// With a `split_id`, the split record goes between the system program
+    /// and the payees, followed by the instructions sysvar if the memo comes
+    /// from the transaction. The payer's receipt log may follow the payees
+    /// to record the split.
+    Split {
+        /// Total lamports to split
+        amount: u64,
+        /// Where lamports left over after rounding go
+        remainder: RemainderPolicy,
+        /// How payees listed more than once are handled
+        duplicates: DuplicatePolicy,
+        /// How shares too small to leave a payee rent-exempt are handled
+        dust: DustPolicy,
+        /// Id that makes retrying the split safe
+        split_id: Option<[u8; 32]>,
+        /// Where the split's memo comes from, if it needs one
+        memo: MemoPolicy,
+    },
+
+    /// Split `amount` lamports between the payees in proportion to their
+    /// weights, handing out any remainder according to `remainder`. A
+    /// `split_id`, `memo` and a program-owned payer are handled as for
+    /// `Split`, so a program-owned payer can't be given a `split_id`.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Debit lamports
+    /// 1. `[]`                 System program
+    /// 2. ..2+N `[writable]`   Payees, in the same order as `weights`
+    ///
+    /// With a `split_id`, the split record goes between the system program
+    /// and the payees, followed by the instructions sysvar if the memo comes
+    /// from the transaction. The payer's receipt log may follow the payees
+    /// to record the split.
+    WeightedSplit {
+        /// Total lamports to split
+        amount: u64,
+        /// One weight per payee account
+        weights: Weights,
+        /// Where lamports left over after rounding go
+        remainder: RemainderPolicy,
+        /// How payees listed more than once are handled
+        duplicates: DuplicatePolicy,
+        /// How shares too small to leave a payee rent-exempt are handled
+        dust: DustPolicy,
+        /// Id that makes retrying the split safe
+        split_id: Option<[u8; 32]>,
+        /// Where the split's memo comes from, if it needs one
+        memo: MemoPolicy,
+    },
+
+    /// Create a split group that stores recipients and weights at the
+    /// program address derived from the authority and `id`.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Authority, funds the group account
+    /// 1. `[writable]`         Split group, program address of `[b"split_group", authority, id]`
+    /// 2. `[]`                 System program
+    CreateSplitGroup {
+        /// Identifier distinguishing the authority's groups
+        id: u64,
+        /// Recipient addresses, in payment order
+        recipients: Vec<Pubkey>,
+        /// One weight per recipient
+        weights: Weights,
+        /// Where lamports left over after rounding go
+        remainder: RemainderPolicy,
+    },
+
+    /// Replace the recipients, weights and remainder policy of a split group.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]`   Authority
+    /// 1. `[writable]` Split group
+    UpdateSplitGroup {
+        /// Recipient addresses, in payment order
+        recipients: Vec<Pubkey>,
+        /// One weight per recipient
+        weights: Weights,
+        /// Where lamports left over after rounding go
+        remainder: RemainderPolicy,
+    },
+
+    /// Close a split group and return its lamports.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]`   Authority
+    /// 1. `[writable]` Split group
+    /// 2. `[writable]` Destination for the group's lamports
+    CloseSplitGroup,
+
+    /// Split `amount` lamports between the recipients stored in a split group.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Debit lamports
+    /// 1. `[]`                 System program
+    /// 2. `[]`                 Split group
+    /// 3. ..3+N `[writable]`   Payees, matching the group's recipients in order
+    ///
+    /// Ledgers of any of the payees may follow the payees to be credited
+    /// with their payments, then the payer's receipt log to record the split.
+    ExecuteSplitGroup {
+        /// Total lamports to split
+        amount: u64,
+        /// How shares too small to leave a payee rent-exempt are handled
+        dust: DustPolicy,
+    },
+
+    /// Create a paged distribution at the program address derived from the
+    /// authority and `id`, with room for `capacity` recipients.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Authority, funds the distribution account
+    /// 1. `[writable]`         Distribution, program address of `[b"distribution", authority, id]`
+    /// 2. `[]`                 System program
+    CreateDistribution {
+        /// Identifier distinguishing the authority's distributions
+        id: u64,
+        /// Total lamports to distribute
+        amount: u64,
+        /// Most recipients the distribution can hold
+        capacity: u32,
+        /// Where lamports left over after rounding go
+        remainder: RemainderPolicy,
+    },
+
+    /// Append recipients to a distribution that hasn't started yet. Each
+    /// recipient may be listed once, and neither the system program nor the
+    /// distribution itself may be a recipient.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]`   Authority
+    /// 1. `[writable]` Distribution
+    AddDistributionRecipients {
+        /// Recipient addresses, in payment order
+        recipients: Vec<Pubkey>,
+        /// One share per recipient
+        shares: Vec<u64>,
+    },
+
+    /// Work out what each recipient is owed and fund the distribution with
+    /// their total. A remainder kept by the payer is never transferred. No
+    /// recipients can be added afterwards.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Authority, debited the lamports owed to recipients
+    /// 1. `[writable]`         Distribution
+    /// 2. `[]`                 System program
+    StartDistribution,
+
+    /// Pay the next page of recipients of a started distribution. Anyone may
+    /// call it. `cursor` must match the distribution's cursor, so a retried
+    /// page fails instead of paying anyone twice.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]`       Distribution
+    /// 1. ..1+N `[writable]` The next N recipients, in order
+    ContinueDistribution {
+        /// Index of the first recipient in the page
+        cursor: u32,
+    },
+
+    /// Close a distribution that is still open or has been fully paid, and
+    /// return its lamports.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]`   Authority
+    /// 1. `[writable]` Distribution
+    /// 2. `[writable]` Destination for the distribution's lamports
+    CloseDistribution,
+
+    /// Create an escrow vault at the program address derived from the
+    /// depositor and `id`, with room for `capacity` payees.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Depositor, funds the escrow account
+    /// 1. `[writable]`         Escrow, program address of `[b"escrow", depositor, id]`
+    /// 2. `[]`                 System program
+    CreateEscrow {
+        /// Identifier distinguishing the depositor's escrows
+        id: u64,
+        /// Most payees the escrow can hold entitlements for
+        capacity: u32,
+    },
+
+    /// Deposit `amount` lamports into an escrow and credit each payee with
+    /// their share. Payees are listed in the instruction data rather than
+    /// passed as accounts. A remainder left with the payer is not deposited.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Depositor
+    /// 1. `[writable]`         Escrow
+    /// 2. `[]`                 System program
+    DepositToEscrow {
+        /// Total lamports to split
+        amount: u64,
+        /// Payees to credit
+        payees: Vec<Pubkey>,
+        /// One weight per payee
+        weights: Weights,
+        /// Where lamports left over after rounding go
+        remainder: RemainderPolicy,
+    },
+
+    /// Withdraw everything the signing payee is owed by an escrow.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Payee
+    /// 1. `[writable]`         Escrow
+    Withdraw,
+
+    /// Close an escrow once every payee has withdrawn, and return its lamports.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]`   Depositor
+    /// 1. `[writable]` Escrow
+    /// 2. `[writable]` Destination for the escrow's lamports
+    CloseEscrow,
+
+    /// Split `amount` tokens from a token account between the payee token
+    /// accounts with `transfer_checked`. The split is even unless `weights`
+    /// are given.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]`       Source token account
+    /// 1. `[]`               Token mint
+    /// 2. `[signer]`         Source account owner or delegate
+    /// 3. `[]`               SPL Token program
+    /// 4. ..4+N `[writable]` Payee token accounts of the same mint
+    TokenSplit {
+        /// Total tokens to split, in base units
+        amount: u64,
+        /// Decimals of the mint, checked by the token program
+        decimals: u8,
+        /// One weight per payee, or `None` for an even split
+        weights: Option<Weights>,
+        /// Where tokens left over after rounding go
+        remainder: RemainderPolicy,
+        /// How payee token accounts listed more than once are handled
+        duplicates: DuplicatePolicy,
+    },
+
+    /// Create a subscription at the program address derived from the
+    /// authority and `id` that splits `amount` lamports between the
+    /// recipients every `interval`, funded with `deposit` lamports. The
+    /// subscription can be topped up with plain system transfers.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Authority, funds the subscription account
+    /// 1. `[writable]`         Subscription, program address of `[b"subscription", authority, id]`
+    /// 2. `[]`                 System program
+    CreateSubscription {
+        /// Identifier distinguishing the authority's subscriptions
+        id: u64,
+        /// Lamports split every interval
+        amount: u64,
+        /// Time between payments
+        interval: Interval,
+        /// Lamports moved into the subscription on creation
+        deposit: u64,
+        /// Recipient addresses, in payment order
+        recipients: Vec<Pubkey>,
+        /// One weight per recipient
+        weights: Weights,
+        /// Where lamports left over after rounding go
+        remainder: RemainderPolicy,
+    },
+
+    /// Pay a subscription's due payment and move the next due time forward
+    /// one interval. Anyone can crank a subscription.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]`       Subscription
+    /// 1. ..1+N `[writable]` Payees, matching the subscription's recipients in order
+    Crank,
+
+    /// Cancel a subscription and return all of its lamports. Nothing is paid
+    /// for the current, unfinished period.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]`   Authority
+    /// 1. `[writable]` Subscription
+    /// 2. `[writable]` Destination for the subscription's lamports
+    CancelSubscription,
+
+    /// Cancel a subscription, paying the recipients for the elapsed part of
+    /// the current period and refunding the rest. Any due payment must be
+    /// cranked first.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]`         Authority
+    /// 1. `[writable]`       Subscription
+    /// 2. `[writable]`       Destination for the subscription's lamports
+    /// 3. ..3+N `[writable]` Payees, matching the subscription's recipients in order
+    RefundSubscription,
+
+    /// Lock `amount` lamports in a vesting schedule at the program address
+    /// derived from the authority and `id`, split between the recipients.
+    /// Each grant unlocks linearly from `start` over `duration` seconds,
+    /// with nothing claimable until `cliff` seconds after `start`. A
+    /// remainder left with the payer is not locked.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Authority, funds the schedule
+    /// 1. `[writable]`         Vesting, program address of `[b"vesting", authority, id]`
+    /// 2. `[]`                 System program
+    CreateVesting {
+        /// Identifier distinguishing the authority's schedules
+        id: u64,
+        /// Total lamports to lock
+        amount: u64,
+        /// Unix time vesting starts
+        start: i64,
+        /// Seconds after `start` before anything can be claimed
+        cliff: i64,
+        /// Seconds after `start` until everything has vested
+        duration: i64,
+        /// Recipient addresses
+        recipients: Vec<Pubkey>,
+        /// One weight per recipient
+        weights: Weights,
+        /// Where lamports left over after rounding go
+        remainder: RemainderPolicy,
+    },
+
+    /// Claim everything vested so far for the signing recipient.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Recipient
+    /// 1. `[writable]`         Vesting
+    ClaimVested,
+
+    /// Stop vesting and return every unvested lamport. Recipients can still
+    /// claim what vested before the revocation.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]`   Authority
+    /// 1. `[writable]` Vesting
+    /// 2. `[writable]` Destination for the unvested lamports
+    RevokeVesting,
+
+    /// Close a vesting schedule once every grant is claimed, and return its
+    /// lamports.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]`   Authority
+    /// 1. `[writable]` Vesting
+    /// 2. `[writable]` Destination for the schedule's lamports
+    CloseVesting,
+
+    /// Create a multisig vault at the program address derived from the
+    /// creator and `id`. The vault is funded with plain system transfers.
+    ///
+    /// Any one signer can split up to `limit` lamports each `period` without
+    /// the others, so `limit` should be what every signer is trusted with.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Creator, funds the vault account
+    /// 1. `[writable]`         Multisig, program address of `[b"multisig", creator, id]`
+    /// 2. `[]`                 System program
+    CreateMultisig {
+        /// Identifier distinguishing the creator's vaults
+        id: u64,
+        /// Keys allowed to authorize splits
+        signers: Vec<Pubkey>,
+        /// Signers needed to authorize a split past `limit`
+        threshold: u8,
+        /// Lamports that can be split on fewer than `threshold` signers each
+        /// period
+        limit: u64,
+        /// Length of the period `limit` applies to
+        period: Interval,
+    },
+
+    /// Split `amount` lamports out of a multisig vault, authorized by the
+    /// signers present in the transaction. One signer is enough while the
+    /// period's splits on fewer than `threshold` signers stay within
+    /// `limit`.
+    ///
+    /// Accounts expected:
+    /// 0. `[writable]`       Multisig
+    /// 1. ..1+N `[writable]` Payees, one per weight, followed by the signing
+    ///    multisig signers
+    MultisigSplit {
+        /// Total lamports to split
+        amount: u64,
+        /// One weight per payee account
+        weights: Weights,
+        /// Where lamports left over after rounding go
+        remainder: RemainderPolicy,
+    },
+
+    /// Propose a split out of a multisig vault, approved by the proposer.
+    /// The proposal's address uses the vault's current proposal count.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Proposer, a multisig signer who funds the proposal account
+    /// 1. `[writable]`         Multisig
+    /// 2. `[writable]`         Proposal, program address of `[b"proposal", multisig, index]`
+    /// 3. `[]`                 System program
+    CreateProposal {
+        /// Total lamports to split
+        amount: u64,
+        /// Recipient addresses, in payment order
+        recipients: Vec<Pubkey>,
+        /// One weight per recipient
+        weights: Weights,
+        /// Where lamports left over after rounding go
+        remainder: RemainderPolicy,
+    },
+
+    /// Approve a proposal. The approval that reaches the required number of
+    /// signers executes the split, and must pass the payees.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]`         Multisig signer
+    /// 1. `[writable]`       Multisig
+    /// 2. `[writable]`       Proposal
+    /// 3. ..3+N `[writable]` Payees, matching the proposal's recipients in order
+    ApproveProposal,
+
+    /// Split `amount` lamports out of the authority's vault in proportion to
+    /// each payee's weight. The vault is a system account at a program
+    /// address, funded with plain transfers, so the authority can be another
+    /// program's address and no hot key needs to hold the lamports. Being a
+    /// system account, it always pays through the system program, signed
+    /// for with its program address.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]`         Authority
+    /// 1. `[writable]`       Vault, program address of `[b"vault", authority]`
+    /// 2. `[]`               System program
+    /// 3. ..3+N `[writable]` Payees, one per weight
+    VaultSplit {
+        /// Total lamports to split
+        amount: u64,
+        /// One weight per payee account
+        weights: Weights,
+        /// Where lamports left over after rounding go
+        remainder: RemainderPolicy,
+        /// How payees listed more than once are handled
+        duplicates: DuplicatePolicy,
+        /// How shares too small to leave a payee rent-exempt are handled
+        dust: DustPolicy,
+    },
+
+    /// Create the payer's receipt log at the program address derived from
+    /// the payer, with room for `capacity` receipts. Once it exists, passing
+    /// it after the payees of a `Legacy`, `Split`, `WeightedSplit` or
+    /// `ExecuteSplitGroup` appends a receipt for the split.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Payer, funds the receipt log account
+    /// 1. `[writable]`         Receipt log, program address of `[b"receipts", payer]`
+    /// 2. `[]`                 System program
+    CreateReceiptLog {
+        /// Most receipts held before the oldest is overwritten
+        capacity: u32,
+    },
+
+    /// Close the payer's receipt log and return its lamports.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]`   Payer
+    /// 1. `[writable]` Receipt log
+    /// 2. `[writable]` Destination for the receipt log's lamports
+    CloseReceiptLog,
+
+    /// Create the ledger recording what `payee` receives from a split group,
+    /// at the program address derived from the group and payee. Anyone may
+    /// fund it, but the payee must be one of the group's recipients.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer, writable]` Funds the ledger account
+    /// 1. `[]`                 Split group
+    /// 2. `[writable]`         Ledger, program address of `[b"ledger", group, payee]`
+    /// 3. `[]`                 System program
+    CreatePayeeLedger {
+        /// Payee whose earnings the ledger records
+        payee: Pubkey,
+    },
+
+    /// Close a payee ledger and return its lamports.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]`   Split group authority
+    /// 1. `[]`         Split group
+    /// 2. `[writable]` Ledger
+    /// 3. `[writable]` Destination for the ledger's lamports
+    ClosePayeeLedger,
+
+    /// Work out what a `WeightedSplit`, or an even `Split` if `weights` is
+    /// `None`, would pay without transferring anything, and log it as a
+    /// `QuoteEvent`. The payer doesn't sign, so any split can be previewed by
+    /// simulating a transaction.
+    ///
+    /// Accounts expected:
+    /// 0. `[]`           Payer
+    /// 1. ..1+N `[]`     Payees, one per weight
+    Quote {
+        /// Total lamports to split
+        amount: u64,
+        /// One weight per payee account, or `None` for an even split
+        weights: Option<Weights>,
+        /// Where lamports left over after rounding go
+        remainder: RemainderPolicy,
+        /// How payees listed more than once are handled
+        duplicates: DuplicatePolicy,
+        /// How shares too small to leave a payee rent-exempt are handled
+        dust: DustPolicy,
+    },
+
+    /// Close a proposal and return its lamports. A proposal that hasn't been
+    /// executed is cancelled.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]`   Proposer
+    /// 1. `[writable]` Proposal
+    /// 2. `[writable]` Destination for the proposal's lamports
+    CloseProposal,
+
+    /// Cancel a started distribution and return what it hasn't paid out,
+    /// along with its rent, to the destination. Recipients after the cursor
+    /// are not paid.
+    ///
+    /// Accounts expected:
+    /// 0. `[signer]`   Authority
+    /// 1. `[writable]` Distribution
+    /// 2. `[writable]` Destination for the distribution's lamports
+    CancelDistribution,
+}
+
+impl SplitInstruction {
+    /// Decodes instruction data, accepting both the legacy and tagged encodings
+    pub fn unpack(input: &[u8]) -> Result<Self, ProgramError> {
+        let (version, rest) = input.split_first().ok_or(SplitError::InvalidInstruction)?;
+        if *version == INSTRUCTION_VERSION && input.len() != LEGACY_INSTRUCTION_LEN {
+            return Self::try_from_slice(rest).map_err(|_| SplitError::InvalidInstruction.into());
+        }
+
+        // Like the original program, read the amount from the first 8 bytes
+        // and ignore the rest
+        let amount = input
+            .get(..LEGACY_INSTRUCTION_LEN)
+            .and_then(|slice| slice.try_into().ok())
+            .map(u64::from_le_bytes)
+            .ok_or(SplitError::UnsupportedVersion)?;
+        Ok(Self::Legacy { amount })
+    }
+
+    /// Encodes the instruction, using the raw 8-byte payload for `Legacy`
+    pub fn pack(&self) -> Vec<u8> {
+        match self {
+            Self::Legacy { amount } => amount.to_le_bytes().to_vec(),
+            _ => {
+                let mut buf = vec![INSTRUCTION_VERSION];
+                buf.extend(self.try_to_vec().unwrap());
+                buf
+            }
+        }
+    }
+}
+
+/// Account metas shared by every split: payer, system program, the split
+/// record if there is a split id, the instructions sysvar if the memo comes
+/// from the transaction, then payees
+fn split_account_metas(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    split_id: Option<&[u8; 32]>,
+    memo: &MemoPolicy,
+    payees: &[Pubkey],
+) -> Vec<AccountMeta> {
+    let mut accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    if let Some(split_id) = split_id {
+        let (record, _) = SplitRecord::find_address(program_id, payer, split_id);
+        accounts.push(AccountMeta::new(record, false));
+    }
+    if *memo == MemoPolicy::Transaction {
+        accounts.push(AccountMeta::new_readonly(sysvar::instructions::id(), false));
+    }
+    accounts.extend(payees.iter().map(|payee| AccountMeta::new(*payee, false)));
+    accounts
+}
+
+/// Creates a `Split` instruction that keeps any remainder with the payer,
+/// rejects duplicate payees and pays dust shares anyway
+pub fn split(program_id: &Pubkey, payer: &Pubkey, payees: &[Pubkey], amount: u64) -> Instruction {
+    split_with_options(
+        program_id,
+        payer,
+        payees,
+        amount,
+        RemainderPolicy::Payer,
+        DuplicatePolicy::Reject,
+        DustPolicy::Allow,
+        None,
+        MemoPolicy::None,
+    )
+}
+
+/// Creates a `Split` instruction with every policy given. With
+/// `MemoPolicy::Transaction`, add an SPL Memo instruction such as
+/// `spl_memo::build_memo` to the transaction.
+#[allow(clippy::too_many_arguments)]
+pub fn split_with_options(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    payees: &[Pubkey],
+    amount: u64,
+    remainder: RemainderPolicy,
+    duplicates: DuplicatePolicy,
+    dust: DustPolicy,
+    split_id: Option<[u8; 32]>,
+    memo: MemoPolicy,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: split_account_metas(program_id, payer, split_id.as_ref(), &memo, payees),
+        data: SplitInstruction::Split {
+            amount,
+            remainder,
+            duplicates,
+            dust,
+            split_id,
+            memo,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `WeightedSplit` instruction. A memo from the transaction is
+/// handled as for `split_with_options`.
+#[allow(clippy::too_many_arguments)]
+pub fn weighted_split(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    payees: &[Pubkey],
+    amount: u64,
+    weights: Weights,
+    remainder: RemainderPolicy,
+    duplicates: DuplicatePolicy,
+    dust: DustPolicy,
+    split_id: Option<[u8; 32]>,
+    memo: MemoPolicy,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: split_account_metas(program_id, payer, split_id.as_ref(), &memo, payees),
+        data: SplitInstruction::WeightedSplit {
+            amount,
+            weights,
+            remainder,
+            duplicates,
+            dust,
+            split_id,
+            memo,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `CreateSplitGroup` instruction for the group at the program
+/// address derived from `authority` and `id`
+pub fn create_split_group(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    id: u64,
+    recipients: Vec<Pubkey>,
+    weights: Weights,
+    remainder: RemainderPolicy,
+) -> Instruction {
+    let (group, _) = SplitGroup::find_address(program_id, authority, id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(group, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SplitInstruction::CreateSplitGroup {
+            id,
+            recipients,
+            weights,
+            remainder,
+        }
+        .pack(),
+    }
+}
+
+/// Creates an `UpdateSplitGroup` instruction
+pub fn update_split_group(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    group: &Pubkey,
+    recipients: Vec<Pubkey>,
+    weights: Weights,
+    remainder: RemainderPolicy,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*group, false),
+        ],
+        data: SplitInstruction::UpdateSplitGroup {
+            recipients,
+            weights,
+            remainder,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `CloseSplitGroup` instruction
+pub fn close_split_group(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    group: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*group, false),
+            AccountMeta::new(*destination, false),
+        ],
+        data: SplitInstruction::CloseSplitGroup.pack(),
+    }
+}
+
+/// Creates an `ExecuteSplitGroup` instruction, `payees` must match the
+/// group's recipients in order
+pub fn execute_split_group(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    group: &Pubkey,
+    payees: &[Pubkey],
+    amount: u64,
+    dust: DustPolicy,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+        AccountMeta::new_readonly(*group, false),
+    ];
+    accounts.extend(payees.iter().map(|payee| AccountMeta::new(*payee, false)));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: SplitInstruction::ExecuteSplitGroup { amount, dust }.pack(),
+    }
+}
+
+/// Creates a `CreateDistribution` instruction for the distribution at the
+/// program address derived from `authority` and `id`
+pub fn create_distribution(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    id: u64,
+    amount: u64,
+    capacity: u32,
+    remainder: RemainderPolicy,
+) -> Instruction {
+    let (distribution, _) = PagedDistribution::find_address(program_id, authority, id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(distribution, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SplitInstruction::CreateDistribution {
+            id,
+            amount,
+            capacity,
+            remainder,
+        }
+        .pack(),
+    }
+}
+
+/// Creates an `AddDistributionRecipients` instruction
+pub fn add_distribution_recipients(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    distribution: &Pubkey,
+    recipients: Vec<Pubkey>,
+    shares: Vec<u64>,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*distribution, false),
+        ],
+        data: SplitInstruction::AddDistributionRecipients { recipients, shares }.pack(),
+    }
+}
+
+/// Creates a `StartDistribution` instruction
+pub fn start_distribution(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    distribution: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(*distribution, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SplitInstruction::StartDistribution.pack(),
+    }
+}
+
+/// Creates a `ContinueDistribution` instruction paying `recipients`, which
+/// must be the distribution's recipients starting at `cursor`
+pub fn continue_distribution(
+    program_id: &Pubkey,
+    distribution: &Pubkey,
+    cursor: u32,
+    recipients: &[Pubkey],
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new(*distribution, false)];
+    accounts.extend(
+        recipients
+            .iter()
+            .map(|recipient| AccountMeta::new(*recipient, false)),
+    );
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: SplitInstruction::ContinueDistribution { cursor }.pack(),
+    }
+}
+
+/// Creates a `CloseDistribution` instruction
+pub fn close_distribution(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    distribution: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*distribution, false),
+            AccountMeta::new(*destination, false),
+        ],
+        data: SplitInstruction::CloseDistribution.pack(),
+    }
+}
+
+/// Creates a `CancelDistribution` instruction
+pub fn cancel_distribution(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    distribution: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*distribution, false),
+            AccountMeta::new(*destination, false),
+        ],
+        data: SplitInstruction::CancelDistribution.pack(),
+    }
+}
+
+/// Creates a `CreateEscrow` instruction for the escrow at the program address
+/// derived from `depositor` and `id`
+pub fn create_escrow(
+    program_id: &Pubkey,
+    depositor: &Pubkey,
+    id: u64,
+    capacity: u32,
+) -> Instruction {
+    let (escrow, _) = Escrow::find_address(program_id, depositor, id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*depositor, true),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SplitInstruction::CreateEscrow { id, capacity }.pack(),
+    }
+}
+
+/// Creates a `DepositToEscrow` instruction
+pub fn deposit_to_escrow(
+    program_id: &Pubkey,
+    depositor: &Pubkey,
+    escrow: &Pubkey,
+    amount: u64,
+    payees: Vec<Pubkey>,
+    weights: Weights,
+    remainder: RemainderPolicy,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*depositor, true),
+            AccountMeta::new(*escrow, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SplitInstruction::DepositToEscrow {
+            amount,
+            payees,
+            weights,
+            remainder,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `Withdraw` instruction
+pub fn withdraw(program_id: &Pubkey, payee: &Pubkey, escrow: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payee, true),
+            AccountMeta::new(*escrow, false),
+        ],
+        data: SplitInstruction::Withdraw.pack(),
+    }
+}
+
+/// Creates a `CloseEscrow` instruction
+pub fn close_escrow(
+    program_id: &Pubkey,
+    depositor: &Pubkey,
+    escrow: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*depositor, true),
+            AccountMeta::new(*escrow, false),
+            AccountMeta::new(*destination, false),
+        ],
+        data: SplitInstruction::CloseEscrow.pack(),
+    }
+}
+
+/// Creates a `TokenSplit` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn token_split(
+    program_id: &Pubkey,
+    source: &Pubkey,
+    mint: &Pubkey,
+    authority: &Pubkey,
+    payees: &[Pubkey],
+    amount: u64,
+    decimals: u8,
+    weights: Option<Weights>,
+    remainder: RemainderPolicy,
+    duplicates: DuplicatePolicy,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(*source, false),
+        AccountMeta::new_readonly(*mint, false),
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ];
+    accounts.extend(payees.iter().map(|payee| AccountMeta::new(*payee, false)));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: SplitInstruction::TokenSplit {
+            amount,
+            decimals,
+            weights,
+            remainder,
+            duplicates,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `CreateSubscription` instruction for the subscription at the
+/// program address derived from `authority` and `id`
+#[allow(clippy::too_many_arguments)]
+pub fn create_subscription(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    id: u64,
+    amount: u64,
+    interval: Interval,
+    deposit: u64,
+    recipients: Vec<Pubkey>,
+    weights: Weights,
+    remainder: RemainderPolicy,
+) -> Instruction {
+    let (subscription, _) = Subscription::find_address(program_id, authority, id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(subscription, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SplitInstruction::CreateSubscription {
+            id,
+            amount,
+            interval,
+            deposit,
+            recipients,
+            weights,
+            remainder,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `Crank` instruction, `payees` must match the subscription's
+/// recipients in order
+pub fn crank(program_id: &Pubkey, subscription: &Pubkey, payees: &[Pubkey]) -> Instruction {
+    let mut accounts = vec![AccountMeta::new(*subscription, false)];
+    accounts.extend(payees.iter().map(|payee| AccountMeta::new(*payee, false)));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: SplitInstruction::Crank.pack(),
+    }
+}
+
+/// Creates a `CancelSubscription` instruction
+pub fn cancel_subscription(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    subscription: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*subscription, false),
+            AccountMeta::new(*destination, false),
+        ],
+        data: SplitInstruction::CancelSubscription.pack(),
+    }
+}
+
+/// Creates a `RefundSubscription` instruction, `payees` must match the
+/// subscription's recipients in order
+pub fn refund_subscription(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    subscription: &Pubkey,
+    destination: &Pubkey,
+    payees: &[Pubkey],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(*subscription, false),
+        AccountMeta::new(*destination, false),
+    ];
+    accounts.extend(payees.iter().map(|payee| AccountMeta::new(*payee, false)));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: SplitInstruction::RefundSubscription.pack(),
+    }
+}
+
+/// Creates a `CreateVesting` instruction for the schedule at the program
+/// address derived from `authority` and `id`
+#[allow(clippy::too_many_arguments)]
+pub fn create_vesting(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    id: u64,
+    amount: u64,
+    start: i64,
+    cliff: i64,
+    duration: i64,
+    recipients: Vec<Pubkey>,
+    weights: Weights,
+    remainder: RemainderPolicy,
+) -> Instruction {
+    let (vesting, _) = Vesting::find_address(program_id, authority, id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*authority, true),
+            AccountMeta::new(vesting, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SplitInstruction::CreateVesting {
+            id,
+            amount,
+            start,
+            cliff,
+            duration,
+            recipients,
+            weights,
+            remainder,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `ClaimVested` instruction
+pub fn claim_vested(program_id: &Pubkey, recipient: &Pubkey, vesting: &Pubkey) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*recipient, true),
+            AccountMeta::new(*vesting, false),
+        ],
+        data: SplitInstruction::ClaimVested.pack(),
+    }
+}
+
+/// Creates a `RevokeVesting` instruction
+pub fn revoke_vesting(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    vesting: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*vesting, false),
+            AccountMeta::new(*destination, false),
+        ],
+        data: SplitInstruction::RevokeVesting.pack(),
+    }
+}
+
+/// Creates a `CloseVesting` instruction
+pub fn close_vesting(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    vesting: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new(*vesting, false),
+            AccountMeta::new(*destination, false),
+        ],
+        data: SplitInstruction::CloseVesting.pack(),
+    }
+}
+
+/// Creates a `CreateMultisig` instruction for the vault at the program
+/// address derived from `creator` and `id`
+pub fn create_multisig(
+    program_id: &Pubkey,
+    creator: &Pubkey,
+    id: u64,
+    signers: Vec<Pubkey>,
+    threshold: u8,
+    limit: u64,
+    period: Interval,
+) -> Instruction {
+    let (multisig, _) = Multisig::find_address(program_id, creator, id);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*creator, true),
+            AccountMeta::new(multisig, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SplitInstruction::CreateMultisig {
+            id,
+            signers,
+            threshold,
+            limit,
+            period,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `MultisigSplit` instruction signed by `signers`
+pub fn multisig_split(
+    program_id: &Pubkey,
+    multisig: &Pubkey,
+    payees: &[Pubkey],
+    signers: &[Pubkey],
+    amount: u64,
+    weights: Weights,
+    remainder: RemainderPolicy,
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new(*multisig, false)];
+    accounts.extend(payees.iter().map(|payee| AccountMeta::new(*payee, false)));
+    accounts.extend(
+        signers
+            .iter()
+            .map(|signer| AccountMeta::new_readonly(*signer, true)),
+    );
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: SplitInstruction::MultisigSplit {
+            amount,
+            weights,
+            remainder,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `CreateProposal` instruction for `multisig`'s proposal `index`,
+/// which must be the vault's current proposal count
+#[allow(clippy::too_many_arguments)]
+pub fn create_proposal(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    multisig: &Pubkey,
+    index: u64,
+    amount: u64,
+    recipients: Vec<Pubkey>,
+    weights: Weights,
+    remainder: RemainderPolicy,
+) -> Instruction {
+    let (proposal, _) = Proposal::find_address(program_id, multisig, index);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*proposer, true),
+            AccountMeta::new(*multisig, false),
+            AccountMeta::new(proposal, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SplitInstruction::CreateProposal {
+            amount,
+            recipients,
+            weights,
+            remainder,
+        }
+        .pack(),
+    }
+}
+
+/// Creates an `ApproveProposal` instruction, `payees` must match the
+/// proposal's recipients if this approval executes it
+pub fn approve_proposal(
+    program_id: &Pubkey,
+    signer: &Pubkey,
+    multisig: &Pubkey,
+    proposal: &Pubkey,
+    payees: &[Pubkey],
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*signer, true),
+        AccountMeta::new(*multisig, false),
+        AccountMeta::new(*proposal, false),
+    ];
+    accounts.extend(payees.iter().map(|payee| AccountMeta::new(*payee, false)));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: SplitInstruction::ApproveProposal.pack(),
+    }
+}
+
+/// Creates a `CloseProposal` instruction
+pub fn close_proposal(
+    program_id: &Pubkey,
+    proposer: &Pubkey,
+    proposal: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*proposer, true),
+            AccountMeta::new(*proposal, false),
+            AccountMeta::new(*destination, false),
+        ],
+        data: SplitInstruction::CloseProposal.pack(),
+    }
+}
+
+/// Creates a `VaultSplit` instruction paying from `authority`'s vault
+#[allow(clippy::too_many_arguments)]
+pub fn vault_split(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    payees: &[Pubkey],
+    amount: u64,
+    weights: Weights,
+    remainder: RemainderPolicy,
+    duplicates: DuplicatePolicy,
+    dust: DustPolicy,
+) -> Instruction {
+    let (vault, _) = find_vault_address(program_id, authority);
+    let mut accounts = vec![
+        AccountMeta::new_readonly(*authority, true),
+        AccountMeta::new(vault, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(payees.iter().map(|payee| AccountMeta::new(*payee, false)));
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: SplitInstruction::VaultSplit {
+            amount,
+            weights,
+            remainder,
+            duplicates,
+            dust,
+        }
+        .pack(),
+    }
+}
+
+/// Creates a `CreateReceiptLog` instruction for `payer`'s receipt log
+pub fn create_receipt_log(program_id: &Pubkey, payer: &Pubkey, capacity: u32) -> Instruction {
+    let (receipt_log, _) = ReceiptLog::find_address(program_id, payer);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new(receipt_log, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SplitInstruction::CreateReceiptLog { capacity }.pack(),
+    }
+}
+
+/// Creates a `CloseReceiptLog` instruction
+pub fn close_receipt_log(program_id: &Pubkey, payer: &Pubkey, destination: &Pubkey) -> Instruction {
+    let (receipt_log, _) = ReceiptLog::find_address(program_id, payer);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*payer, true),
+            AccountMeta::new(receipt_log, false),
+            AccountMeta::new(*destination, false),
+        ],
+        data: SplitInstruction::CloseReceiptLog.pack(),
+    }
+}
+
+/// Appends `payer`'s receipt log to a split instruction so the split is
+/// recorded in it
+pub fn with_receipt_log(
+    mut instruction: Instruction,
+    program_id: &Pubkey,
+    payer: &Pubkey,
+) -> Instruction {
+    let (receipt_log, _) = ReceiptLog::find_address(program_id, payer);
+    instruction
+        .accounts
+        .push(AccountMeta::new(receipt_log, false));
+    instruction
+}
+
+/// Creates a `CreatePayeeLedger` instruction for `payee`'s ledger in `group`
+pub fn create_payee_ledger(
+    program_id: &Pubkey,
+    funder: &Pubkey,
+    group: &Pubkey,
+    payee: &Pubkey,
+) -> Instruction {
+    let (ledger, _) = PayeeLedger::find_address(program_id, group, payee);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new(*funder, true),
+            AccountMeta::new_readonly(*group, false),
+            AccountMeta::new(ledger, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SplitInstruction::CreatePayeeLedger { payee: *payee }.pack(),
+    }
+}
+
+/// Creates a `ClosePayeeLedger` instruction
+pub fn close_payee_ledger(
+    program_id: &Pubkey,
+    authority: &Pubkey,
+    group: &Pubkey,
+    payee: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    let (ledger, _) = PayeeLedger::find_address(program_id, group, payee);
+    Instruction {
+        program_id: *program_id,
+        accounts: vec![
+            AccountMeta::new_readonly(*authority, true),
+            AccountMeta::new_readonly(*group, false),
+            AccountMeta::new(ledger, false),
+            AccountMeta::new(*destination, false),
+        ],
+        data: SplitInstruction::ClosePayeeLedger.pack(),
+    }
+}
+
+/// Appends the ledgers of `payees` in `group` to an `ExecuteSplitGroup`
+/// instruction so their payments are credited. Apply before
+/// `with_receipt_log`, which must stay last.
+pub fn with_payee_ledgers(
+    mut instruction: Instruction,
+    program_id: &Pubkey,
+    group: &Pubkey,
+    payees: &[Pubkey],
+) -> Instruction {
+    instruction.accounts.extend(payees.iter().map(|payee| {
+        let (ledger, _) = PayeeLedger::find_address(program_id, group, payee);
+        AccountMeta::new(ledger, false)
+    }));
+    instruction
+}
+
+/// Creates a `Quote` instruction
+#[allow(clippy::too_many_arguments)]
+pub fn quote(
+    program_id: &Pubkey,
+    payer: &Pubkey,
+    payees: &[Pubkey],
+    amount: u64,
+    weights: Option<Weights>,
+    remainder: RemainderPolicy,
+    duplicates: DuplicatePolicy,
+    dust: DustPolicy,
+) -> Instruction {
+    let mut accounts = vec![AccountMeta::new_readonly(*payer, false)];
+    accounts.extend(
+        payees
+            .iter()
+            .map(|payee| AccountMeta::new_readonly(*payee, false)),
+    );
+    Instruction {
+        program_id: *program_id,
+        accounts,
+        data: SplitInstruction::Quote {
+            amount,
+            weights,
+            remainder,
+            duplicates,
+            dust,
+        }
+        .pack(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unpack_legacy_trailing_bytes() {
+        // The original program read the first 8 bytes and ignored the rest
+        let mut data = 7_u64.to_le_bytes().to_vec();
+        data.extend_from_slice(&[0xff; 4]);
+        assert_eq!(
+            SplitInstruction::unpack(&data).unwrap(),
+            SplitInstruction::Legacy { amount: 7 }
+        );
+
+        // Unless the amount's low byte is the version byte
+        let mut data = 257_u64.to_le_bytes().to_vec();
+        data.extend_from_slice(&[0xff; 4]);
+        assert_eq!(
+            SplitInstruction::unpack(&data).unwrap_err(),
+            SplitError::InvalidInstruction.into()
+        );
+        assert_eq!(
+            SplitInstruction::unpack(&257_u64.to_le_bytes()).unwrap(),
+            SplitInstruction::Legacy { amount: 257 }
+        );
+
+        let tagged = SplitInstruction::CloseProposal;
+        assert_eq!(SplitInstruction::unpack(&tagged.pack()).unwrap(), tagged);
+        assert_eq!(
+            SplitInstruction::unpack(&[]).unwrap_err(),
+            SplitError::InvalidInstruction.into()
+        );
+    }
+}
@@ -0,0 +1,2519 @@
+//! Program state processor
+
+use crate::{
+    error::SplitError,
+    event::{QuoteEvent, SplitEvent},
+    instruction::SplitInstruction,
+    split::{
+        self, Distribution, DuplicatePolicy, DustPolicy, MemoPolicy, Plan, RemainderPolicy,
+        Weights, MAX_PAYEES,
+    },
+    state::{
+        find_vault_address, AccountKind, DistributionRecipient, DistributionStatus, Escrow, Grant,
+        Interval, Multisig, PagedDistribution, PayeeLedger, ProgramAccount, Proposal, ReceiptLog,
+        SplitGroup, SplitRecord, Subscription, Vesting, DISTRIBUTION_SEED, ESCROW_SEED,
+        LEDGER_SEED, MAX_DISTRIBUTION_RECIPIENTS, MAX_ESCROW_PAYEES, MAX_RECEIPTS, MULTISIG_SEED,
+        PROPOSAL_SEED, RECEIPT_LOG_SEED, SPLIT_GROUP_SEED, SPLIT_RECORD_SEED, SUBSCRIPTION_SEED,
+        VAULT_SEED, VESTING_SEED,
+    },
+};
+use solana_program::{
+    account_info::{next_account_info, AccountInfo},
+    clock::Clock,
+    entrypoint::ProgramResult,
+    hash::Hash,
+    log::sol_log,
+    msg,
+    program::{invoke, invoke_signed},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    system_instruction::{allocate, assign, create_account, transfer, SystemInstruction},
+    system_program::ID as SYSTEM_PROGRAM_ID,
+    sysvar::{self, instructions::load_instruction_at, rent::Rent, Sysvar},
+};
+use std::{fmt::Write, ops::Deref, slice::Iter};
+
+/// Room for the longest transfer log line, with a `u64` amount and two
+/// base58 addresses
+const TRANSFER_LOG_CAPACITY: usize = 144;
+
+/// Payee accounts picked out of an instruction's accounts, held on the stack
+/// rather than collected into a `Vec`
+#[derive(Clone, Copy)]
+struct PayeeAccounts<'a, 'b> {
+    accounts: [&'a AccountInfo<'b>; MAX_PAYEES],
+    len: usize,
+}
+
+impl<'a, 'b> PayeeAccounts<'a, 'b> {
+    /// Takes every account from `accounts`, failing unless there are between
+    /// 1 and `MAX_PAYEES` of them
+    fn new<I: Iterator<Item = &'a AccountInfo<'b>>>(mut accounts: I) -> Result<Self, ProgramError> {
+        let first = accounts.next().ok_or(SplitError::InvalidPayeeCount)?;
+        let mut payees = Self {
+            accounts: [first; MAX_PAYEES],
+            len: 1,
+        };
+        for account in accounts {
+            if payees.len == MAX_PAYEES {
+                msg!("Tried to split between more than {} accounts", MAX_PAYEES);
+                return Err(SplitError::InvalidPayeeCount.into());
+            }
+            payees.accounts[payees.len] = account;
+            payees.len += 1;
+        }
+        Ok(payees)
+    }
+}
+
+impl<'a, 'b> Deref for PayeeAccounts<'a, 'b> {
+    type Target = [&'a AccountInfo<'b>];
+
+    fn deref(&self) -> &Self::Target {
+        &self.accounts[..self.len]
+    }
+}
+
+/// Program state handler
+pub struct Processor;
+
+impl Processor {
+    /// Takes the signing payer and the system program that follow it
+    fn next_payer_account<'a, 'b>(
+        accounts_iter: &mut Iter<'a, AccountInfo<'b>>,
+    ) -> Result<&'a AccountInfo<'b>, ProgramError> {
+        // First account should be signed account of payer
+        let payer_account = next_account_info(accounts_iter)?;
+        if !payer_account.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+
+        // Second account should be system account for transfer
+        let system_account = next_account_info(accounts_iter)?;
+        if system_account.key.ne(&SYSTEM_PROGRAM_ID) {
+            msg!("System account not specified as second account");
+            return Err(SplitError::MissingSystemProgram.into());
+        }
+
+        Ok(payer_account)
+    }
+
+    /// Takes the remaining accounts as payees
+    fn payee_accounts<'a, 'b>(
+        accounts_iter: &mut Iter<'a, AccountInfo<'b>>,
+    ) -> Result<PayeeAccounts<'a, 'b>, ProgramError> {
+        PayeeAccounts::new(accounts_iter)
+    }
+
+    /// Checks the payer and system program, records `split_id` if given,
+    /// finds the memo `memo` requires and collects the payee accounts,
+    /// followed by the payer's receipt log if one was passed
+    #[allow(clippy::type_complexity)]
+    fn split_accounts<'a, 'b>(
+        program_id: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+        split_id: Option<&[u8; 32]>,
+        memo: &MemoPolicy,
+    ) -> Result<
+        (
+            &'a AccountInfo<'b>,
+            PayeeAccounts<'a, 'b>,
+            Option<(&'a AccountInfo<'b>, ReceiptLog)>,
+            Option<String>,
+        ),
+        ProgramError,
+    > {
+        // Iterating accounts is safer then indexing
+        let accounts_iter = &mut accounts.iter();
+        let payer_account = Self::next_payer_account(accounts_iter)?;
+        if let Some(split_id) = split_id {
+            // The payer funds the record through the system program, which
+            // can only debit system-owned accounts
+            if payer_account.owner == program_id {
+                msg!("Payer {} is owned by this program", payer_account.key);
+                return Err(SplitError::ProgramOwnedPayerSplitId.into());
+            }
+            let record_account = next_account_info(accounts_iter)?;
+            // The system program was checked by `next_payer_account`
+            Self::record_split_id(
+                program_id,
+                payer_account,
+                record_account,
+                &accounts[1],
+                split_id,
+            )?;
+        }
+        let memo = Self::split_memo(memo, accounts_iter)?;
+        let (accounts, receipt_log) =
+            Self::receipt_log_account(program_id, payer_account.key, accounts_iter.as_slice())?;
+        let payee_accounts = Self::payee_accounts(&mut accounts.iter())?;
+        Ok((payer_account, payee_accounts, receipt_log, memo))
+    }
+
+    /// Finds the memo a split carries under `policy`, taking the
+    /// instructions sysvar from `accounts_iter` when the memo must come from
+    /// an SPL Memo instruction in the same transaction
+    fn split_memo(
+        policy: &MemoPolicy,
+        accounts_iter: &mut Iter<AccountInfo>,
+    ) -> Result<Option<String>, ProgramError> {
+        let memo = match policy {
+            MemoPolicy::None => return Ok(None),
+            MemoPolicy::Inline(memo) => memo.clone(),
+            MemoPolicy::Transaction => {
+                let instructions_account = next_account_info(accounts_iter)?;
+                if !sysvar::instructions::check_id(instructions_account.key) {
+                    msg!(
+                        "Instructions sysvar should be {}",
+                        sysvar::instructions::id()
+                    );
+                    return Err(SplitError::InvalidAccountAddress.into());
+                }
+                let data = instructions_account.try_borrow_data()?;
+                let memo_instruction = (0..)
+                    .map(|index| load_instruction_at(index, &data))
+                    .take_while(Result::is_ok)
+                    .flatten()
+                    .find(|instruction| {
+                        instruction.program_id == spl_memo::id()
+                            || instruction.program_id == spl_memo::v1::id()
+                    })
+                    .ok_or(SplitError::MissingMemo)?;
+                String::from_utf8(memo_instruction.data).map_err(|_| SplitError::InvalidMemo)?
+            }
+        };
+        split::check_memo(&memo)?;
+        Ok(Some(memo))
+    }
+
+    /// Creates the record of `split_id` at its program address, failing if
+    /// the payer already paid a split with that id
+    fn record_split_id<'a>(
+        program_id: &Pubkey,
+        payer_account: &AccountInfo<'a>,
+        record_account: &AccountInfo<'a>,
+        system_account: &AccountInfo<'a>,
+        split_id: &[u8; 32],
+    ) -> ProgramResult {
+        let (address, bump) = SplitRecord::find_address(program_id, payer_account.key, split_id);
+        if address != *record_account.key {
+            msg!("Split record address should be {}", address);
+            return Err(SplitError::InvalidAccountAddress.into());
+        }
+        if record_account.owner == program_id {
+            msg!("Split id already used, recorded at {}", address);
+            return Err(SplitError::SplitIdUsed.into());
+        }
+        Self::create_program_account(
+            program_id,
+            payer_account,
+            record_account,
+            system_account,
+            SplitRecord::LEN,
+            &[
+                SPLIT_RECORD_SEED,
+                payer_account.key.as_ref(),
+                split_id,
+                &[bump],
+            ],
+        )?;
+
+        SplitRecord {
+            kind: AccountKind::SplitRecord,
+            payer: *payer_account.key,
+            split_id: *split_id,
+            slot: Clock::get()?.slot,
+        }
+        .save(record_account)
+    }
+
+    /// Takes the payer's receipt log off the end of `accounts`. The last
+    /// account is only treated as a receipt log when this program owns it
+    /// and it holds one, so it can't be confused with a payee.
+    #[allow(clippy::type_complexity)]
+    fn receipt_log_account<'a, 'b>(
+        program_id: &Pubkey,
+        payer: &Pubkey,
+        accounts: &'a [AccountInfo<'b>],
+    ) -> Result<
+        (
+            &'a [AccountInfo<'b>],
+            Option<(&'a AccountInfo<'b>, ReceiptLog)>,
+        ),
+        ProgramError,
+    > {
+        let (last, rest) = match accounts.split_last() {
+            Some((last, rest))
+                if last.owner == program_id
+                    && last.data.borrow().first() == Some(&(AccountKind::ReceiptLog as u8)) =>
+            {
+                (last, rest)
+            }
+            _ => return Ok((accounts, None)),
+        };
+        let receipt_log = ReceiptLog::load(last, program_id)?;
+        if receipt_log.payer != *payer {
+            msg!("Receipt log belongs to {}", receipt_log.payer);
+            return Err(SplitError::InvalidAccountAddress.into());
+        }
+        Ok((rest, Some((last, receipt_log))))
+    }
+
+    /// Appends a receipt for a completed split to the payer's receipt log
+    fn append_receipt(
+        receipt_log_account: &AccountInfo,
+        mut receipt_log: ReceiptLog,
+        payee_accounts: &[&AccountInfo],
+        distribution: &Distribution,
+    ) -> ProgramResult {
+        let payees = payee_accounts.iter().map(|account| *account.key).collect();
+        receipt_log.append(Clock::get()?.slot, payees, distribution.amounts.clone());
+        msg!(
+            "receipt {} hashes to {}",
+            receipt_log.count - 1,
+            receipt_log.last_hash
+        );
+        receipt_log.save(receipt_log_account)
+    }
+
+    /// Creates a program-owned account at the program address for `seeds`,
+    /// funded by `payer_account` to be rent exempt. Lamports already sent to
+    /// the address are kept and topped up, so funding it first can't stop
+    /// the account from being created.
+    fn create_program_account<'a>(
+        program_id: &Pubkey,
+        payer_account: &AccountInfo<'a>,
+        new_account: &AccountInfo<'a>,
+        system_account: &AccountInfo<'a>,
+        space: usize,
+        seeds: &[&[u8]],
+    ) -> ProgramResult {
+        if system_account.key.ne(&SYSTEM_PROGRAM_ID) {
+            return Err(SplitError::MissingSystemProgram.into());
+        }
+        let required = Rent::get()?.minimum_balance(space);
+        let accounts = [
+            payer_account.clone(),
+            new_account.clone(),
+            system_account.clone(),
+        ];
+        if new_account.lamports() == 0 {
+            return invoke_signed(
+                &create_account(
+                    payer_account.key,
+                    new_account.key,
+                    required,
+                    space as u64,
+                    program_id,
+                ),
+                &accounts,
+                &[seeds],
+            );
+        }
+
+        // `create_account` refuses an address that already holds lamports
+        let shortfall = required.saturating_sub(new_account.lamports());
+        if shortfall > 0 {
+            invoke(
+                &transfer(payer_account.key, new_account.key, shortfall),
+                &accounts,
+            )?;
+        }
+        invoke_signed(
+            &allocate(new_account.key, space as u64),
+            &accounts,
+            &[seeds],
+        )?;
+        invoke_signed(&assign(new_account.key, program_id), &accounts, &[seeds])
+    }
+
+    /// Moves lamports out of an account owned by this program without a CPI
+    fn transfer_program_lamports(
+        source: &AccountInfo,
+        destination: &AccountInfo,
+        amount: u64,
+    ) -> ProgramResult {
+        let source_lamports = source
+            .lamports()
+            .checked_sub(amount)
+            .ok_or(ProgramError::InsufficientFunds)?;
+        // A legacy split may list the payer as a payee, which leaves its
+        // balance as it was
+        if source.key == destination.key {
+            return Ok(());
+        }
+        let destination_lamports = destination
+            .lamports()
+            .checked_add(amount)
+            .ok_or(SplitError::Overflow)?;
+        **source.try_borrow_mut_lamports()? = source_lamports;
+        **destination.try_borrow_mut_lamports()? = destination_lamports;
+        Ok(())
+    }
+
+    /// Checks a program-owned account can pay out `amount` and stay rent
+    /// exempt
+    fn check_spendable(source: &AccountInfo, amount: u64) -> ProgramResult {
+        let spendable = source
+            .lamports()
+            .saturating_sub(Rent::get()?.minimum_balance(source.data_len()));
+        if amount > spendable {
+            msg!("{} lamports needed, {} available", amount, spendable);
+            return Err(ProgramError::InsufficientFunds);
+        }
+        Ok(())
+    }
+
+    /// Moves all lamports out of a program-owned account and clears its data
+    fn close_program_account(account: &AccountInfo, destination: &AccountInfo) -> ProgramResult {
+        let lamports = account.lamports();
+        **destination.try_borrow_mut_lamports()? = destination
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(SplitError::Overflow)?;
+        **account.try_borrow_mut_lamports()? = 0;
+        for byte in account.try_borrow_mut_data()?.iter_mut() {
+            *byte = 0;
+        }
+        Ok(())
+    }
+
+    /// Transfers each payee's amount from the payer and logs the remainder.
+    /// A payer owned by this program, such as a multisig or subscription, is
+    /// debited directly and kept rent exempt. Any other payer, including a
+    /// vault, pays through the system program, which is handed all of the
+    /// instruction's `accounts` so none has to be cloned, and `signer_seeds`
+    /// sign for a payer at a program address. The split is recorded in
+    /// `receipt_log` if one is given, and its event carries `memo`, with
+    /// `split_id` hashed into its id.
+    #[allow(clippy::too_many_arguments)]
+    fn transfer_distribution<'a>(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo<'a>],
+        payer_account: &AccountInfo<'a>,
+        payee_accounts: &[&AccountInfo<'a>],
+        distribution: &Distribution,
+        signer_seeds: &[&[&[u8]]],
+        receipt_log: Option<(&AccountInfo<'a>, ReceiptLog)>,
+        split_id: Option<&[u8; 32]>,
+        memo: Option<&str>,
+    ) -> ProgramResult {
+        let direct_debit = payer_account.owner == program_id;
+        if direct_debit {
+            Self::check_spendable(payer_account, distribution.total())?;
+        }
+        // One transfer is built and pointed at each payee in turn, its data
+        // re-encoded in place for each amount
+        let mut instruction = transfer(payer_account.key, payer_account.key, 0);
+        let payer_address = payer_account.key.to_string();
+        let mut line = String::with_capacity(TRANSFER_LOG_CAPACITY);
+        for (account, amount) in payee_accounts.iter().zip(&distribution.amounts) {
+            if *amount == 0 {
+                continue;
+            }
+            if direct_debit {
+                Self::transfer_program_lamports(payer_account, account, *amount)?;
+            } else {
+                instruction.accounts[1].pubkey = *account.key;
+                bincode::serialize_into(
+                    &mut instruction.data[..],
+                    &SystemInstruction::Transfer { lamports: *amount },
+                )
+                .map_err(|_| ProgramError::InvalidInstructionData)?;
+                invoke_signed(&instruction, accounts, signer_seeds)?;
+            }
+            Self::log_transfer(&mut line, *amount, &payer_address, account.key);
+        }
+        msg!(
+            "remainder of {} lamports {}",
+            distribution.remainder,
+            distribution.policy.description()
+        );
+        Self::log_split_event(
+            payer_account.key,
+            None,
+            payee_accounts,
+            distribution,
+            split_id,
+            memo,
+        )?;
+        match receipt_log {
+            Some((receipt_log_account, receipt_log)) => Self::append_receipt(
+                receipt_log_account,
+                receipt_log,
+                payee_accounts,
+                distribution,
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// Logs a transfer of `amount` lamports from the encoded `payer` address
+    /// to `payee`. A split encodes its payer once and rewrites `line` for
+    /// every payee rather than formatting a new message each time.
+    fn log_transfer(line: &mut String, amount: u64, payer: &str, payee: &Pubkey) {
+        line.clear();
+        // Writing to a `String` can't fail
+        write!(
+            line,
+            "transferred {} lamports from {} to {}",
+            amount, payer, payee
+        )
+        .unwrap();
+        sol_log(line);
+    }
+
+    /// Logs the event recording a completed split
+    fn log_split_event(
+        payer: &Pubkey,
+        mint: Option<&Pubkey>,
+        payee_accounts: &[&AccountInfo],
+        distribution: &Distribution,
+        split_id: Option<&[u8; 32]>,
+        memo: Option<&str>,
+    ) -> ProgramResult {
+        SplitEvent::new(
+            Clock::get()?.slot,
+            payer,
+            mint,
+            payee_accounts,
+            distribution,
+            split_id,
+            memo,
+        )
+        .log();
+        Ok(())
+    }
+
+    /// Splits `amount` between the payee accounts, applying `dust` to any
+    /// share that would leave its payee below the rent-exempt minimum
+    fn distribute_to_accounts(
+        payee_accounts: &[&AccountInfo],
+        amount: u64,
+        shares: &[u64],
+        remainder: RemainderPolicy,
+        dust: DustPolicy,
+    ) -> Result<Distribution, ProgramError> {
+        let shortfalls = Self::rent_shortfalls(payee_accounts)?;
+        let (distribution, _) = split::distribute_without_dust(
+            amount,
+            payee_accounts,
+            shares,
+            remainder,
+            &shortfalls[..payee_accounts.len()],
+            dust,
+        )?;
+        Ok(distribution)
+    }
+
+    /// Lamports each of the payee accounts needs to become rent-exempt, held
+    /// on the stack and read as the first `accounts.len()` entries
+    fn rent_shortfalls(accounts: &[&AccountInfo]) -> Result<[u64; MAX_PAYEES], ProgramError> {
+        let rent = Rent::get()?;
+        let mut shortfalls = [0; MAX_PAYEES];
+        for (shortfall, account) in shortfalls.iter_mut().zip(accounts) {
+            *shortfall = rent
+                .minimum_balance(account.data_len())
+                .saturating_sub(account.lamports());
+        }
+        Ok(shortfalls)
+    }
+
+    /// Plans a weighted split between the payee accounts, returning the
+    /// accounts that would be paid with the plan
+    #[allow(clippy::type_complexity)]
+    fn plan_split<'a, 'b>(
+        payer: &Pubkey,
+        payee_accounts: &[&'a AccountInfo<'b>],
+        amount: u64,
+        weights: &Weights,
+        remainder: RemainderPolicy,
+        duplicates: DuplicatePolicy,
+        dust: DustPolicy,
+    ) -> Result<(PayeeAccounts<'a, 'b>, Plan), ProgramError> {
+        let shortfalls = Self::rent_shortfalls(payee_accounts)?;
+        let plan = split::plan_distribution(
+            payer,
+            payee_accounts,
+            amount,
+            weights,
+            remainder,
+            duplicates,
+            &shortfalls[..payee_accounts.len()],
+            dust,
+        )?;
+        let accounts = PayeeAccounts::new(plan.indices.iter().map(|index| payee_accounts[*index]))?;
+        Ok((accounts, plan))
+    }
+
+    /// Rejects or merges repeated payees, returning the distinct payee
+    /// accounts with their shares
+    fn distinct_payee_accounts<'a, 'b>(
+        payer: &Pubkey,
+        payee_accounts: &[&'a AccountInfo<'b>],
+        shares: &[u64],
+        duplicates: DuplicatePolicy,
+    ) -> Result<(PayeeAccounts<'a, 'b>, Vec<u64>), ProgramError> {
+        let distinct = split::distinct_payees(Some(payer), payee_accounts, shares, duplicates)?;
+        let accounts =
+            PayeeAccounts::new(distinct.indices.iter().map(|index| payee_accounts[*index]))?;
+        Ok((accounts, distinct.shares))
+    }
+
+    /// Processes a [Legacy](enum.SplitInstruction.html) instruction the way
+    /// the original program did: every payee account listed is paid
+    /// `amount / N`, including a repeated payee or the payer itself, which
+    /// the tagged instructions reject
+    pub fn process_legacy_split(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+    ) -> ProgramResult {
+        let (payer_account, payee_accounts, receipt_log, _) =
+            Self::split_accounts(program_id, accounts, None, &MemoPolicy::None)?;
+        let distribution = split::distribute(
+            amount,
+            &[1; MAX_PAYEES][..payee_accounts.len()],
+            RemainderPolicy::Payer,
+        )?;
+        Self::transfer_distribution(
+            program_id,
+            accounts,
+            payer_account,
+            &payee_accounts,
+            &distribution,
+            &[],
+            receipt_log,
+            None,
+            None,
+        )
+    }
+
+    /// Processes an even split of `amount` between the payee accounts
+    #[allow(clippy::too_many_arguments)]
+    pub fn process_split(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        remainder: RemainderPolicy,
+        duplicates: DuplicatePolicy,
+        dust: DustPolicy,
+        split_id: Option<&[u8; 32]>,
+        memo: &MemoPolicy,
+    ) -> ProgramResult {
+        let (payer_account, payee_accounts, receipt_log, memo) =
+            Self::split_accounts(program_id, accounts, split_id, memo)?;
+        Self::weighted_split(
+            program_id,
+            accounts,
+            payer_account,
+            &payee_accounts,
+            amount,
+            &Weights::Shares(vec![1; payee_accounts.len()]),
+            remainder,
+            duplicates,
+            dust,
+            &[],
+            receipt_log,
+            split_id,
+            memo.as_deref(),
+        )
+    }
+
+    /// Processes a split of `amount` in proportion to each payee's weight
+    #[allow(clippy::too_many_arguments)]
+    pub fn process_weighted_split(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        weights: &Weights,
+        remainder: RemainderPolicy,
+        duplicates: DuplicatePolicy,
+        dust: DustPolicy,
+        split_id: Option<&[u8; 32]>,
+        memo: &MemoPolicy,
+    ) -> ProgramResult {
+        let (payer_account, payee_accounts, receipt_log, memo) =
+            Self::split_accounts(program_id, accounts, split_id, memo)?;
+        Self::weighted_split(
+            program_id,
+            accounts,
+            payer_account,
+            &payee_accounts,
+            amount,
+            weights,
+            remainder,
+            duplicates,
+            dust,
+            &[],
+            receipt_log,
+            split_id,
+            memo.as_deref(),
+        )
+    }
+
+    /// Splits `amount` from the payer in proportion to each payee's weight.
+    /// `accounts` are all of the instruction's accounts.
+    #[allow(clippy::too_many_arguments)]
+    fn weighted_split<'a>(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo<'a>],
+        payer_account: &AccountInfo<'a>,
+        payee_accounts: &[&AccountInfo<'a>],
+        amount: u64,
+        weights: &Weights,
+        remainder: RemainderPolicy,
+        duplicates: DuplicatePolicy,
+        dust: DustPolicy,
+        signer_seeds: &[&[&[u8]]],
+        receipt_log: Option<(&AccountInfo<'a>, ReceiptLog)>,
+        split_id: Option<&[u8; 32]>,
+        memo: Option<&str>,
+    ) -> ProgramResult {
+        let (payee_accounts, plan) = Self::plan_split(
+            payer_account.key,
+            payee_accounts,
+            amount,
+            weights,
+            remainder,
+            duplicates,
+            dust,
+        )?;
+        Self::transfer_distribution(
+            program_id,
+            accounts,
+            payer_account,
+            &payee_accounts,
+            &plan.distribution,
+            signer_seeds,
+            receipt_log,
+            split_id,
+            memo,
+        )
+    }
+
+    /// Processes a [Quote](enum.SplitInstruction.html) instruction
+    pub fn process_quote(
+        accounts: &[AccountInfo],
+        amount: u64,
+        weights: Option<&Weights>,
+        remainder: RemainderPolicy,
+        duplicates: DuplicatePolicy,
+        dust: DustPolicy,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let payer_account = next_account_info(accounts_iter)?;
+        let payee_accounts = Self::payee_accounts(accounts_iter)?;
+
+        let even = Weights::Shares(vec![1; payee_accounts.len()]);
+        let (_, plan) = Self::plan_split(
+            payer_account.key,
+            &payee_accounts,
+            amount,
+            weights.unwrap_or(&even),
+            remainder,
+            duplicates,
+            dust,
+        )?;
+        QuoteEvent::new(payer_account.key, &payee_accounts, &plan).log();
+        Ok(())
+    }
+
+    /// Processes a [VaultSplit](enum.SplitInstruction.html) instruction
+    #[allow(clippy::too_many_arguments)]
+    pub fn process_vault_split(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        weights: &Weights,
+        remainder: RemainderPolicy,
+        duplicates: DuplicatePolicy,
+        dust: DustPolicy,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let authority_account = next_account_info(accounts_iter)?;
+        let vault_account = next_account_info(accounts_iter)?;
+        let system_account = next_account_info(accounts_iter)?;
+        let payee_accounts = Self::payee_accounts(accounts_iter)?;
+
+        // The authority may itself be a program address signed for by the
+        // calling program
+        if !authority_account.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if system_account.key.ne(&SYSTEM_PROGRAM_ID) {
+            return Err(SplitError::MissingSystemProgram.into());
+        }
+        let (address, bump) = find_vault_address(program_id, authority_account.key);
+        if address != *vault_account.key {
+            msg!("Vault address should be {}", address);
+            return Err(SplitError::InvalidAccountAddress.into());
+        }
+
+        Self::weighted_split(
+            program_id,
+            accounts,
+            vault_account,
+            &payee_accounts,
+            amount,
+            weights,
+            remainder,
+            duplicates,
+            dust,
+            &[&[VAULT_SEED, authority_account.key.as_ref(), &[bump]]],
+            None,
+            None,
+            None,
+        )
+    }
+
+    /// Processes a [CreateReceiptLog](enum.SplitInstruction.html) instruction
+    pub fn process_create_receipt_log(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        capacity: u32,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let payer_account = next_account_info(accounts_iter)?;
+        let receipt_log_account = next_account_info(accounts_iter)?;
+        let system_account = next_account_info(accounts_iter)?;
+
+        if !payer_account.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if capacity == 0 || capacity as usize > MAX_RECEIPTS {
+            msg!(
+                "Receipt log capacity is {}, max is {}",
+                capacity,
+                MAX_RECEIPTS
+            );
+            return Err(SplitError::CapacityExceeded.into());
+        }
+
+        let (address, bump) = ReceiptLog::find_address(program_id, payer_account.key);
+        if address != *receipt_log_account.key {
+            msg!("Receipt log address should be {}", address);
+            return Err(SplitError::InvalidAccountAddress.into());
+        }
+        Self::create_program_account(
+            program_id,
+            payer_account,
+            receipt_log_account,
+            system_account,
+            ReceiptLog::space(capacity as usize),
+            &[RECEIPT_LOG_SEED, payer_account.key.as_ref(), &[bump]],
+        )?;
+
+        ReceiptLog {
+            kind: AccountKind::ReceiptLog,
+            payer: *payer_account.key,
+            bump,
+            capacity,
+            count: 0,
+            last_hash: Hash::default(),
+            receipts: Vec::new(),
+        }
+        .save(receipt_log_account)
+    }
+
+    /// Processes a [CloseReceiptLog](enum.SplitInstruction.html) instruction
+    pub fn process_close_receipt_log(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let payer_account = next_account_info(accounts_iter)?;
+        let receipt_log_account = next_account_info(accounts_iter)?;
+        let destination_account = next_account_info(accounts_iter)?;
+
+        let receipt_log = ReceiptLog::load(receipt_log_account, program_id)?;
+        Self::check_authority(payer_account, &receipt_log.payer)?;
+        Self::close_program_account(receipt_log_account, destination_account)
+    }
+
+    /// Processes a [CreateSplitGroup](enum.SplitInstruction.html) instruction
+    pub fn process_create_split_group(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        id: u64,
+        recipients: Vec<Pubkey>,
+        weights: Weights,
+        remainder: RemainderPolicy,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let authority_account = next_account_info(accounts_iter)?;
+        let group_account = next_account_info(accounts_iter)?;
+        let system_account = next_account_info(accounts_iter)?;
+
+        if !authority_account.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        SplitGroup::validate(&recipients, &weights)?;
+
+        let (address, bump) = SplitGroup::find_address(program_id, authority_account.key, id);
+        if address != *group_account.key {
+            msg!("Split group address should be {}", address);
+            return Err(SplitError::InvalidAccountAddress.into());
+        }
+        Self::create_program_account(
+            program_id,
+            authority_account,
+            group_account,
+            system_account,
+            SplitGroup::LEN,
+            &[
+                SPLIT_GROUP_SEED,
+                authority_account.key.as_ref(),
+                &id.to_le_bytes(),
+                &[bump],
+            ],
+        )?;
+
+        SplitGroup {
+            kind: AccountKind::SplitGroup,
+            authority: *authority_account.key,
+            id,
+            bump,
+            remainder,
+            recipients,
+            weights,
+        }
+        .save(group_account)
+    }
+
+    /// Checks the payee accounts are the stored recipients, in order
+    fn check_recipients(payee_accounts: &[&AccountInfo], recipients: &[Pubkey]) -> ProgramResult {
+        if payee_accounts.len() != recipients.len()
+            || payee_accounts
+                .iter()
+                .zip(recipients)
+                .any(|(account, recipient)| account.key != recipient)
+        {
+            msg!("Payees should be {:?}", recipients);
+            return Err(SplitError::PayeeMismatch.into());
+        }
+        Ok(())
+    }
+
+    /// Checks `authority_account` is `authority` and signed the transaction
+    fn check_authority(authority_account: &AccountInfo, authority: &Pubkey) -> ProgramResult {
+        if !authority_account.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if authority != authority_account.key {
+            return Err(SplitError::InvalidAuthority.into());
+        }
+        Ok(())
+    }
+
+    /// Loads a split group and checks `authority_account` signed as its authority
+    fn load_split_group_as_authority(
+        program_id: &Pubkey,
+        group_account: &AccountInfo,
+        authority_account: &AccountInfo,
+    ) -> Result<SplitGroup, ProgramError> {
+        let group = SplitGroup::load(group_account, program_id)?;
+        Self::check_authority(authority_account, &group.authority)?;
+        Ok(group)
+    }
+
+    /// Processes an [UpdateSplitGroup](enum.SplitInstruction.html) instruction
+    pub fn process_update_split_group(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        recipients: Vec<Pubkey>,
+        weights: Weights,
+        remainder: RemainderPolicy,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let authority_account = next_account_info(accounts_iter)?;
+        let group_account = next_account_info(accounts_iter)?;
+
+        let mut group =
+            Self::load_split_group_as_authority(program_id, group_account, authority_account)?;
+        SplitGroup::validate(&recipients, &weights)?;
+        group.recipients = recipients;
+        group.weights = weights;
+        group.remainder = remainder;
+        group.save(group_account)
+    }
+
+    /// Processes a [CloseSplitGroup](enum.SplitInstruction.html) instruction
+    pub fn process_close_split_group(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let authority_account = next_account_info(accounts_iter)?;
+        let group_account = next_account_info(accounts_iter)?;
+        let destination_account = next_account_info(accounts_iter)?;
+
+        Self::load_split_group_as_authority(program_id, group_account, authority_account)?;
+        Self::close_program_account(group_account, destination_account)
+    }
+
+    /// Processes an [ExecuteSplitGroup](enum.SplitInstruction.html) instruction
+    pub fn process_execute_split_group(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        dust: DustPolicy,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let payer_account = Self::next_payer_account(accounts_iter)?;
+        let group_account = next_account_info(accounts_iter)?;
+        let (remaining_accounts, receipt_log) =
+            Self::receipt_log_account(program_id, payer_account.key, accounts_iter.as_slice())?;
+
+        let group = SplitGroup::load(group_account, program_id)?;
+        let (payee_accounts, ledger_accounts) =
+            remaining_accounts.split_at(group.recipients.len().min(remaining_accounts.len()));
+        let payee_accounts = PayeeAccounts::new(payee_accounts.iter())
+            .map_err(|_| ProgramError::from(SplitError::PayeeMismatch))?;
+        Self::check_recipients(&payee_accounts, &group.recipients)?;
+
+        let shares = group.weights.to_shares()?;
+        split::distinct_payees(
+            Some(payer_account.key),
+            &group.recipients,
+            &shares,
+            DuplicatePolicy::Reject,
+        )?;
+        let distribution =
+            Self::distribute_to_accounts(&payee_accounts, amount, &shares, group.remainder, dust)?;
+        Self::transfer_distribution(
+            program_id,
+            accounts,
+            payer_account,
+            &payee_accounts,
+            &distribution,
+            &[],
+            receipt_log,
+            None,
+            None,
+        )?;
+        Self::credit_payee_ledgers(
+            program_id,
+            group_account.key,
+            ledger_accounts,
+            &group.recipients,
+            &distribution,
+        )
+    }
+
+    /// Credits each passed payee ledger of `group` with its payee's amount
+    fn credit_payee_ledgers(
+        program_id: &Pubkey,
+        group: &Pubkey,
+        ledger_accounts: &[AccountInfo],
+        recipients: &[Pubkey],
+        distribution: &Distribution,
+    ) -> ProgramResult {
+        if ledger_accounts.is_empty() {
+            return Ok(());
+        }
+        let slot = Clock::get()?.slot;
+        let mut credited = Vec::with_capacity(ledger_accounts.len());
+        for ledger_account in ledger_accounts {
+            let mut ledger = PayeeLedger::load(ledger_account, program_id)?;
+            if ledger.group != *group {
+                msg!("Ledger {} is for another group", ledger_account.key);
+                return Err(SplitError::InvalidAccountAddress.into());
+            }
+            // Crediting the same ledger twice would overstate its total
+            if credited.contains(&ledger.payee) {
+                return Err(SplitError::DuplicatePayee.into());
+            }
+            let index = recipients
+                .iter()
+                .position(|recipient| *recipient == ledger.payee)
+                .ok_or(SplitError::PayeeMismatch)?;
+            ledger.credit(distribution.amounts[index], slot)?;
+            ledger.save(ledger_account)?;
+            credited.push(ledger.payee);
+        }
+        Ok(())
+    }
+
+    /// Processes a [CreatePayeeLedger](enum.SplitInstruction.html) instruction
+    pub fn process_create_payee_ledger(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        payee: &Pubkey,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let funder_account = next_account_info(accounts_iter)?;
+        let group_account = next_account_info(accounts_iter)?;
+        let ledger_account = next_account_info(accounts_iter)?;
+        let system_account = next_account_info(accounts_iter)?;
+
+        if !funder_account.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let group = SplitGroup::load(group_account, program_id)?;
+        if !group.recipients.contains(payee) {
+            msg!("{} is not a recipient of the split group", payee);
+            return Err(SplitError::PayeeMismatch.into());
+        }
+
+        let (address, bump) = PayeeLedger::find_address(program_id, group_account.key, payee);
+        if address != *ledger_account.key {
+            msg!("Ledger address should be {}", address);
+            return Err(SplitError::InvalidAccountAddress.into());
+        }
+        Self::create_program_account(
+            program_id,
+            funder_account,
+            ledger_account,
+            system_account,
+            PayeeLedger::LEN,
+            &[
+                LEDGER_SEED,
+                group_account.key.as_ref(),
+                payee.as_ref(),
+                &[bump],
+            ],
+        )?;
+
+        PayeeLedger {
+            kind: AccountKind::PayeeLedger,
+            group: *group_account.key,
+            payee: *payee,
+            bump,
+            total_received: 0,
+            split_count: 0,
+            last_slot: 0,
+        }
+        .save(ledger_account)
+    }
+
+    /// Processes a [ClosePayeeLedger](enum.SplitInstruction.html) instruction
+    pub fn process_close_payee_ledger(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let authority_account = next_account_info(accounts_iter)?;
+        let group_account = next_account_info(accounts_iter)?;
+        let ledger_account = next_account_info(accounts_iter)?;
+        let destination_account = next_account_info(accounts_iter)?;
+
+        let group = SplitGroup::load(group_account, program_id)?;
+        Self::check_authority(authority_account, &group.authority)?;
+        let ledger = PayeeLedger::load(ledger_account, program_id)?;
+        if ledger.group != *group_account.key {
+            return Err(SplitError::InvalidAccountAddress.into());
+        }
+        Self::close_program_account(ledger_account, destination_account)
+    }
+
+    /// Processes a [CreateDistribution](enum.SplitInstruction.html) instruction
+    pub fn process_create_distribution(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        id: u64,
+        amount: u64,
+        capacity: u32,
+        remainder: RemainderPolicy,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let authority_account = next_account_info(accounts_iter)?;
+        let distribution_account = next_account_info(accounts_iter)?;
+        let system_account = next_account_info(accounts_iter)?;
+
+        if !authority_account.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let capacity = capacity as usize;
+        if capacity == 0 || capacity > MAX_DISTRIBUTION_RECIPIENTS {
+            msg!(
+                "Distribution capacity is {}, max is {}",
+                capacity,
+                MAX_DISTRIBUTION_RECIPIENTS
+            );
+            return Err(SplitError::InvalidPayeeCount.into());
+        }
+
+        let (address, bump) =
+            PagedDistribution::find_address(program_id, authority_account.key, id);
+        if address != *distribution_account.key {
+            msg!("Distribution address should be {}", address);
+            return Err(SplitError::InvalidAccountAddress.into());
+        }
+        Self::create_program_account(
+            program_id,
+            authority_account,
+            distribution_account,
+            system_account,
+            PagedDistribution::space(capacity),
+            &[
+                DISTRIBUTION_SEED,
+                authority_account.key.as_ref(),
+                &id.to_le_bytes(),
+                &[bump],
+            ],
+        )?;
+
+        PagedDistribution {
+            kind: AccountKind::PagedDistribution,
+            authority: *authority_account.key,
+            id,
+            bump,
+            amount,
+            remainder,
+            status: DistributionStatus::Open,
+            cursor: 0,
+            recipients: Vec::new(),
+        }
+        .save(distribution_account)
+    }
+
+    /// Processes an [AddDistributionRecipients](enum.SplitInstruction.html) instruction
+    pub fn process_add_distribution_recipients(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        recipients: Vec<Pubkey>,
+        shares: Vec<u64>,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let authority_account = next_account_info(accounts_iter)?;
+        let distribution_account = next_account_info(accounts_iter)?;
+
+        let mut distribution = PagedDistribution::load(distribution_account, program_id)?;
+        Self::check_authority(authority_account, &distribution.authority)?;
+        if distribution.status != DistributionStatus::Open {
+            return Err(SplitError::InvalidDistributionStatus.into());
+        }
+        if shares.len() != recipients.len() {
+            return Err(SplitError::WeightCountMismatch.into());
+        }
+        let capacity = PagedDistribution::capacity(distribution_account.data_len());
+        if distribution.recipients.len() + recipients.len() > capacity {
+            msg!("Distribution can hold {} recipients", capacity);
+            return Err(SplitError::CapacityExceeded.into());
+        }
+
+        // The distribution can't pay its own account, and each recipient is
+        // listed once across every batch
+        split::distinct_payees(
+            Some(distribution_account.key),
+            &recipients,
+            &shares,
+            DuplicatePolicy::Reject,
+        )?;
+        if let Some(recipient) = recipients.iter().find(|recipient| {
+            distribution
+                .recipients
+                .iter()
+                .any(|existing| existing.address == **recipient)
+        }) {
+            msg!("Payee {} is listed more than once", recipient);
+            return Err(SplitError::DuplicatePayee.into());
+        }
+
+        distribution
+            .recipients
+            .extend(recipients.into_iter().zip(shares).map(|(address, share)| {
+                DistributionRecipient {
+                    address,
+                    share,
+                    amount: 0,
+                }
+            }));
+        distribution.save(distribution_account)
+    }
+
+    /// Processes a [StartDistribution](enum.SplitInstruction.html) instruction
+    pub fn process_start_distribution(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let authority_account = next_account_info(accounts_iter)?;
+        let distribution_account = next_account_info(accounts_iter)?;
+        let system_account = next_account_info(accounts_iter)?;
+
+        let mut distribution = PagedDistribution::load(distribution_account, program_id)?;
+        Self::check_authority(authority_account, &distribution.authority)?;
+        if distribution.status != DistributionStatus::Open {
+            return Err(SplitError::InvalidDistributionStatus.into());
+        }
+        if distribution.recipients.is_empty() {
+            return Err(SplitError::InvalidPayeeCount.into());
+        }
+        if system_account.key.ne(&SYSTEM_PROGRAM_ID) {
+            return Err(SplitError::MissingSystemProgram.into());
+        }
+
+        let shares: Vec<u64> = distribution
+            .recipients
+            .iter()
+            .map(|recipient| recipient.share)
+            .collect();
+        let planned = split::distribute(distribution.amount, &shares, distribution.remainder)?;
+        for (recipient, amount) in distribution.recipients.iter_mut().zip(&planned.amounts) {
+            recipient.amount = *amount;
+        }
+
+        invoke(
+            &transfer(
+                authority_account.key,
+                distribution_account.key,
+                planned.total(),
+            ),
+            &[
+                authority_account.clone(),
+                distribution_account.clone(),
+                system_account.clone(),
+            ],
+        )?;
+        msg!(
+            "funded distribution with {} lamports for {} recipients",
+            planned.total(),
+            distribution.recipients.len()
+        );
+        msg!(
+            "remainder of {} lamports {}",
+            planned.remainder,
+            planned.policy.description()
+        );
+
+        distribution.status = DistributionStatus::Active;
+        distribution.save(distribution_account)
+    }
+
+    /// Processes a [ContinueDistribution](enum.SplitInstruction.html) instruction
+    pub fn process_continue_distribution(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        cursor: u32,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let distribution_account = next_account_info(accounts_iter)?;
+        let payee_accounts = Self::payee_accounts(accounts_iter)?;
+
+        let mut distribution = PagedDistribution::load(distribution_account, program_id)?;
+        if distribution.status == DistributionStatus::Open {
+            return Err(SplitError::InvalidDistributionStatus.into());
+        }
+        // Checked before completion so a retried final page reports the
+        // stale cursor rather than a finished distribution
+        if cursor != distribution.cursor {
+            msg!(
+                "Page at {} requested, next unpaid recipient is {}",
+                cursor,
+                distribution.cursor
+            );
+            return Err(SplitError::StaleCursor.into());
+        }
+        if distribution.status == DistributionStatus::Complete {
+            return Err(SplitError::InvalidDistributionStatus.into());
+        }
+
+        let start = distribution.cursor as usize;
+        let page = distribution
+            .recipients
+            .get(start..start + payee_accounts.len())
+            .ok_or(SplitError::PayeeMismatch)?;
+        let distribution_address = distribution_account.key.to_string();
+        let mut line = String::with_capacity(TRANSFER_LOG_CAPACITY);
+        for (account, recipient) in payee_accounts.iter().zip(page) {
+            if *account.key != recipient.address {
+                msg!("Payee {} should be {}", account.key, recipient.address);
+                return Err(SplitError::PayeeMismatch.into());
+            }
+            Self::transfer_program_lamports(distribution_account, account, recipient.amount)?;
+            Self::log_transfer(
+                &mut line,
+                recipient.amount,
+                &distribution_address,
+                account.key,
+            );
+        }
+
+        distribution.cursor += payee_accounts.len() as u32;
+        if distribution.cursor as usize == distribution.recipients.len() {
+            distribution.status = DistributionStatus::Complete;
+        }
+        msg!(
+            "paid {} of {} recipients",
+            distribution.cursor,
+            distribution.recipients.len()
+        );
+        distribution.save(distribution_account)
+    }
+
+    /// Processes a [CloseDistribution](enum.SplitInstruction.html) instruction
+    pub fn process_close_distribution(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let authority_account = next_account_info(accounts_iter)?;
+        let distribution_account = next_account_info(accounts_iter)?;
+        let destination_account = next_account_info(accounts_iter)?;
+
+        let distribution = PagedDistribution::load(distribution_account, program_id)?;
+        Self::check_authority(authority_account, &distribution.authority)?;
+        if distribution.status == DistributionStatus::Active {
+            msg!("Distribution still owes recipients");
+            return Err(SplitError::InvalidDistributionStatus.into());
+        }
+        Self::close_program_account(distribution_account, destination_account)
+    }
+
+    /// Processes a [CancelDistribution](enum.SplitInstruction.html) instruction
+    pub fn process_cancel_distribution(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let authority_account = next_account_info(accounts_iter)?;
+        let distribution_account = next_account_info(accounts_iter)?;
+        let destination_account = next_account_info(accounts_iter)?;
+
+        let distribution = PagedDistribution::load(distribution_account, program_id)?;
+        Self::check_authority(authority_account, &distribution.authority)?;
+        if distribution.status != DistributionStatus::Active {
+            return Err(SplitError::InvalidDistributionStatus.into());
+        }
+        let unpaid = &distribution.recipients[distribution.cursor as usize..];
+        msg!(
+            "cancelled distribution owing {} lamports to {} of {} recipients",
+            unpaid.iter().map(|recipient| recipient.amount).sum::<u64>(),
+            unpaid.len(),
+            distribution.recipients.len()
+        );
+        Self::close_program_account(distribution_account, destination_account)
+    }
+
+    /// Processes a [CreateEscrow](enum.SplitInstruction.html) instruction
+    pub fn process_create_escrow(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        id: u64,
+        capacity: u32,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let depositor_account = next_account_info(accounts_iter)?;
+        let escrow_account = next_account_info(accounts_iter)?;
+        let system_account = next_account_info(accounts_iter)?;
+
+        if !depositor_account.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let capacity = capacity as usize;
+        if capacity == 0 || capacity > MAX_ESCROW_PAYEES {
+            msg!(
+                "Escrow capacity is {}, max is {}",
+                capacity,
+                MAX_ESCROW_PAYEES
+            );
+            return Err(SplitError::InvalidPayeeCount.into());
+        }
+
+        let (address, bump) = Escrow::find_address(program_id, depositor_account.key, id);
+        if address != *escrow_account.key {
+            msg!("Escrow address should be {}", address);
+            return Err(SplitError::InvalidAccountAddress.into());
+        }
+        Self::create_program_account(
+            program_id,
+            depositor_account,
+            escrow_account,
+            system_account,
+            Escrow::space(capacity),
+            &[
+                ESCROW_SEED,
+                depositor_account.key.as_ref(),
+                &id.to_le_bytes(),
+                &[bump],
+            ],
+        )?;
+
+        Escrow {
+            kind: AccountKind::Escrow,
+            depositor: *depositor_account.key,
+            id,
+            bump,
+            entitlements: Vec::new(),
+        }
+        .save(escrow_account)
+    }
+
+    /// Processes a [DepositToEscrow](enum.SplitInstruction.html) instruction
+    pub fn process_deposit_to_escrow(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        payees: &[Pubkey],
+        weights: &Weights,
+        remainder: RemainderPolicy,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let depositor_account = next_account_info(accounts_iter)?;
+        let escrow_account = next_account_info(accounts_iter)?;
+        let system_account = next_account_info(accounts_iter)?;
+
+        let mut escrow = Escrow::load(escrow_account, program_id)?;
+        Self::check_authority(depositor_account, &escrow.depositor)?;
+        if system_account.key.ne(&SYSTEM_PROGRAM_ID) {
+            return Err(SplitError::MissingSystemProgram.into());
+        }
+        if payees.is_empty() {
+            return Err(SplitError::InvalidPayeeCount.into());
+        }
+        if weights.len() != payees.len() {
+            return Err(SplitError::WeightCountMismatch.into());
+        }
+
+        let shares = weights.to_shares()?;
+        let distribution = split::distribute(amount, &shares, remainder)?;
+        for (payee, amount) in payees.iter().zip(&distribution.amounts) {
+            escrow.credit(payee, *amount)?;
+        }
+        let capacity = Escrow::capacity(escrow_account.data_len());
+        if escrow.entitlements.len() > capacity {
+            msg!("Escrow can hold {} payees", capacity);
+            return Err(SplitError::CapacityExceeded.into());
+        }
+
+        invoke(
+            &transfer(
+                depositor_account.key,
+                escrow_account.key,
+                distribution.total(),
+            ),
+            &[
+                depositor_account.clone(),
+                escrow_account.clone(),
+                system_account.clone(),
+            ],
+        )?;
+        msg!(
+            "deposited {} lamports for {} payees",
+            distribution.total(),
+            payees.len()
+        );
+        msg!(
+            "remainder of {} lamports {}",
+            distribution.remainder,
+            distribution.policy.description()
+        );
+        escrow.save(escrow_account)
+    }
+
+    /// Processes a [Withdraw](enum.SplitInstruction.html) instruction
+    pub fn process_withdraw(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let payee_account = next_account_info(accounts_iter)?;
+        let escrow_account = next_account_info(accounts_iter)?;
+
+        if !payee_account.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let mut escrow = Escrow::load(escrow_account, program_id)?;
+        let entitlement = escrow
+            .entitlements
+            .iter_mut()
+            .find(|entitlement| entitlement.payee == *payee_account.key)
+            .ok_or(SplitError::NothingToWithdraw)?;
+        let amount = entitlement.outstanding();
+        if amount == 0 {
+            return Err(SplitError::NothingToWithdraw.into());
+        }
+        entitlement.withdrawn = entitlement.amount;
+
+        Self::transfer_program_lamports(escrow_account, payee_account, amount)?;
+        msg!(
+            "withdrew {} lamports from {:?} to {:?}",
+            amount,
+            escrow_account.key,
+            payee_account.key
+        );
+        escrow.save(escrow_account)
+    }
+
+    /// Processes a [CloseEscrow](enum.SplitInstruction.html) instruction
+    pub fn process_close_escrow(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let depositor_account = next_account_info(accounts_iter)?;
+        let escrow_account = next_account_info(accounts_iter)?;
+        let destination_account = next_account_info(accounts_iter)?;
+
+        let escrow = Escrow::load(escrow_account, program_id)?;
+        Self::check_authority(depositor_account, &escrow.depositor)?;
+        if escrow.outstanding() > 0 {
+            msg!("{} lamports not yet withdrawn", escrow.outstanding());
+            return Err(SplitError::EntitlementsOutstanding.into());
+        }
+        Self::close_program_account(escrow_account, destination_account)
+    }
+
+    /// Processes a [TokenSplit](enum.SplitInstruction.html) instruction
+    pub fn process_token_split(
+        accounts: &[AccountInfo],
+        amount: u64,
+        decimals: u8,
+        weights: Option<&Weights>,
+        remainder: RemainderPolicy,
+        duplicates: DuplicatePolicy,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let source_account = next_account_info(accounts_iter)?;
+        let mint_account = next_account_info(accounts_iter)?;
+        let authority_account = next_account_info(accounts_iter)?;
+        let token_program_account = next_account_info(accounts_iter)?;
+        let payee_accounts = Self::payee_accounts(accounts_iter)?;
+
+        if !authority_account.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if *token_program_account.key != spl_token::id() {
+            msg!("Token program not specified as fourth account");
+            return Err(SplitError::MissingTokenProgram.into());
+        }
+
+        let shares = match weights {
+            Some(weights) => {
+                if weights.len() != payee_accounts.len() {
+                    return Err(SplitError::WeightCountMismatch.into());
+                }
+                weights.to_shares()?
+            }
+            None => vec![1; payee_accounts.len()],
+        };
+        let (payee_accounts, shares) = Self::distinct_payee_accounts(
+            source_account.key,
+            &payee_accounts,
+            &shares,
+            duplicates,
+        )?;
+        let distribution = split::distribute(amount, &shares, remainder)?;
+
+        for (account, amount) in payee_accounts.iter().zip(&distribution.amounts) {
+            invoke(
+                &spl_token::instruction::transfer_checked(
+                    token_program_account.key,
+                    source_account.key,
+                    mint_account.key,
+                    account.key,
+                    authority_account.key,
+                    &[],
+                    *amount,
+                    decimals,
+                )?,
+                accounts,
+            )?;
+            msg!(
+                "transferred {} tokens from {:?} to {:?}",
+                amount,
+                source_account.key,
+                account.key
+            );
+        }
+        msg!(
+            "remainder of {} tokens {}",
+            distribution.remainder,
+            distribution.policy.description()
+        );
+        Self::log_split_event(
+            source_account.key,
+            Some(mint_account.key),
+            &payee_accounts,
+            &distribution,
+            None,
+            None,
+        )
+    }
+
+    /// Processes a [CreateSubscription](enum.SplitInstruction.html) instruction
+    #[allow(clippy::too_many_arguments)]
+    pub fn process_create_subscription(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        id: u64,
+        amount: u64,
+        interval: Interval,
+        deposit: u64,
+        recipients: Vec<Pubkey>,
+        weights: Weights,
+        remainder: RemainderPolicy,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let authority_account = next_account_info(accounts_iter)?;
+        let subscription_account = next_account_info(accounts_iter)?;
+        let system_account = next_account_info(accounts_iter)?;
+
+        if !authority_account.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        SplitGroup::validate(&recipients, &weights)?;
+        if interval.length() == 0 {
+            return Err(SplitError::InvalidInterval.into());
+        }
+
+        let (address, bump) = Subscription::find_address(program_id, authority_account.key, id);
+        if address != *subscription_account.key {
+            msg!("Subscription address should be {}", address);
+            return Err(SplitError::InvalidAccountAddress.into());
+        }
+        Self::create_program_account(
+            program_id,
+            authority_account,
+            subscription_account,
+            system_account,
+            Subscription::LEN,
+            &[
+                SUBSCRIPTION_SEED,
+                authority_account.key.as_ref(),
+                &id.to_le_bytes(),
+                &[bump],
+            ],
+        )?;
+        if deposit > 0 {
+            invoke(
+                &transfer(authority_account.key, subscription_account.key, deposit),
+                &[
+                    authority_account.clone(),
+                    subscription_account.clone(),
+                    system_account.clone(),
+                ],
+            )?;
+        }
+
+        let next_due = interval
+            .now(&Clock::get()?)
+            .checked_add(interval.length())
+            .ok_or(SplitError::Overflow)?;
+        msg!("first payment due at {}", next_due);
+        Subscription {
+            kind: AccountKind::Subscription,
+            authority: *authority_account.key,
+            id,
+            bump,
+            amount,
+            interval,
+            next_due,
+            remainder,
+            recipients,
+            weights,
+        }
+        .save(subscription_account)
+    }
+
+    /// Processes a [Crank](enum.SplitInstruction.html) instruction
+    pub fn process_crank(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let subscription_account = next_account_info(accounts_iter)?;
+        let payee_accounts = Self::payee_accounts(accounts_iter)?;
+
+        let mut subscription = Subscription::load(subscription_account, program_id)?;
+        Self::check_recipients(&payee_accounts, &subscription.recipients)?;
+        let now = subscription.interval.now(&Clock::get()?);
+        if now < subscription.next_due {
+            msg!("Next payment due at {}, now {}", subscription.next_due, now);
+            return Err(SplitError::PaymentNotDue.into());
+        }
+
+        let shares = subscription.weights.to_shares()?;
+        let distribution = split::distribute(subscription.amount, &shares, subscription.remainder)?;
+        Self::transfer_distribution(
+            program_id,
+            accounts,
+            subscription_account,
+            &payee_accounts,
+            &distribution,
+            &[],
+            None,
+            None,
+            None,
+        )?;
+
+        // Missed periods are paid one crank at a time
+        subscription.next_due = subscription
+            .next_due
+            .checked_add(subscription.interval.length())
+            .ok_or(SplitError::Overflow)?;
+        msg!("next payment due at {}", subscription.next_due);
+        subscription.save(subscription_account)
+    }
+
+    /// Processes a [CancelSubscription](enum.SplitInstruction.html) instruction
+    pub fn process_cancel_subscription(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let authority_account = next_account_info(accounts_iter)?;
+        let subscription_account = next_account_info(accounts_iter)?;
+        let destination_account = next_account_info(accounts_iter)?;
+
+        let subscription = Subscription::load(subscription_account, program_id)?;
+        Self::check_authority(authority_account, &subscription.authority)?;
+        Self::close_program_account(subscription_account, destination_account)
+    }
+
+    /// Processes a [RefundSubscription](enum.SplitInstruction.html) instruction
+    pub fn process_refund_subscription(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let authority_account = next_account_info(accounts_iter)?;
+        let subscription_account = next_account_info(accounts_iter)?;
+        let destination_account = next_account_info(accounts_iter)?;
+        let payee_accounts = Self::payee_accounts(accounts_iter)?;
+
+        let subscription = Subscription::load(subscription_account, program_id)?;
+        Self::check_authority(authority_account, &subscription.authority)?;
+        Self::check_recipients(&payee_accounts, &subscription.recipients)?;
+        let now = subscription.interval.now(&Clock::get()?);
+        if now >= subscription.next_due {
+            msg!(
+                "Payment due at {} must be cranked first",
+                subscription.next_due
+            );
+            return Err(SplitError::PaymentDue.into());
+        }
+
+        let owed = subscription.prorated_amount(now);
+        if owed > 0 {
+            let shares = subscription.weights.to_shares()?;
+            let distribution = split::distribute(owed, &shares, subscription.remainder)?;
+            Self::transfer_distribution(
+                program_id,
+                accounts,
+                subscription_account,
+                &payee_accounts,
+                &distribution,
+                &[],
+                None,
+                None,
+                None,
+            )?;
+        }
+        msg!(
+            "paid {} of {} lamports for the current period",
+            owed,
+            subscription.amount
+        );
+        Self::close_program_account(subscription_account, destination_account)
+    }
+
+    /// Processes a [CreateVesting](enum.SplitInstruction.html) instruction
+    #[allow(clippy::too_many_arguments)]
+    pub fn process_create_vesting(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        id: u64,
+        amount: u64,
+        start: i64,
+        cliff: i64,
+        duration: i64,
+        recipients: Vec<Pubkey>,
+        weights: &Weights,
+        remainder: RemainderPolicy,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let authority_account = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?;
+        let system_account = next_account_info(accounts_iter)?;
+
+        if !authority_account.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        SplitGroup::validate(&recipients, weights)?;
+        Vesting::validate_schedule(cliff, duration)?;
+
+        let (address, bump) = Vesting::find_address(program_id, authority_account.key, id);
+        if address != *vesting_account.key {
+            msg!("Vesting address should be {}", address);
+            return Err(SplitError::InvalidAccountAddress.into());
+        }
+        Self::create_program_account(
+            program_id,
+            authority_account,
+            vesting_account,
+            system_account,
+            Vesting::LEN,
+            &[
+                VESTING_SEED,
+                authority_account.key.as_ref(),
+                &id.to_le_bytes(),
+                &[bump],
+            ],
+        )?;
+
+        let shares = weights.to_shares()?;
+        let distribution = split::distribute(amount, &shares, remainder)?;
+        invoke(
+            &transfer(
+                authority_account.key,
+                vesting_account.key,
+                distribution.total(),
+            ),
+            &[
+                authority_account.clone(),
+                vesting_account.clone(),
+                system_account.clone(),
+            ],
+        )?;
+        msg!(
+            "locked {} lamports for {} recipients",
+            distribution.total(),
+            recipients.len()
+        );
+        msg!(
+            "remainder of {} lamports {}",
+            distribution.remainder,
+            distribution.policy.description()
+        );
+
+        Vesting {
+            kind: AccountKind::Vesting,
+            authority: *authority_account.key,
+            id,
+            bump,
+            start,
+            cliff,
+            duration,
+            revoked: false,
+            grants: recipients
+                .into_iter()
+                .zip(distribution.amounts)
+                .map(|(recipient, amount)| Grant {
+                    recipient,
+                    amount,
+                    claimed: 0,
+                })
+                .collect(),
+        }
+        .save(vesting_account)
+    }
+
+    /// Processes a [ClaimVested](enum.SplitInstruction.html) instruction
+    pub fn process_claim_vested(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let recipient_account = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?;
+
+        if !recipient_account.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let mut vesting = Vesting::load(vesting_account, program_id)?;
+        let now = Clock::get()?.unix_timestamp;
+        let index = vesting
+            .grants
+            .iter()
+            .position(|grant| grant.recipient == *recipient_account.key)
+            .ok_or(SplitError::NothingToWithdraw)?;
+        let vested = vesting.vested(&vesting.grants[index], now);
+        let grant = &mut vesting.grants[index];
+        let amount = vested.saturating_sub(grant.claimed);
+        if amount == 0 {
+            return Err(SplitError::NothingToWithdraw.into());
+        }
+        grant.claimed = vested;
+
+        Self::transfer_program_lamports(vesting_account, recipient_account, amount)?;
+        msg!(
+            "claimed {} lamports from {:?} to {:?}",
+            amount,
+            vesting_account.key,
+            recipient_account.key
+        );
+        vesting.save(vesting_account)
+    }
+
+    /// Processes a [RevokeVesting](enum.SplitInstruction.html) instruction
+    pub fn process_revoke_vesting(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let authority_account = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?;
+        let destination_account = next_account_info(accounts_iter)?;
+
+        let mut vesting = Vesting::load(vesting_account, program_id)?;
+        Self::check_authority(authority_account, &vesting.authority)?;
+        if vesting.revoked {
+            return Err(SplitError::VestingRevoked.into());
+        }
+
+        let now = Clock::get()?.unix_timestamp;
+        let mut unvested = 0u64;
+        for index in 0..vesting.grants.len() {
+            let vested = vesting.vested(&vesting.grants[index], now);
+            let grant = &mut vesting.grants[index];
+            unvested += grant.amount - vested;
+            grant.amount = vested;
+        }
+        vesting.revoked = true;
+
+        Self::transfer_program_lamports(vesting_account, destination_account, unvested)?;
+        msg!("revoked {} unvested lamports", unvested);
+        vesting.save(vesting_account)
+    }
+
+    /// Processes a [CloseVesting](enum.SplitInstruction.html) instruction
+    pub fn process_close_vesting(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let authority_account = next_account_info(accounts_iter)?;
+        let vesting_account = next_account_info(accounts_iter)?;
+        let destination_account = next_account_info(accounts_iter)?;
+
+        let vesting = Vesting::load(vesting_account, program_id)?;
+        Self::check_authority(authority_account, &vesting.authority)?;
+        if vesting.outstanding() > 0 {
+            msg!("{} lamports not yet claimed", vesting.outstanding());
+            return Err(SplitError::EntitlementsOutstanding.into());
+        }
+        Self::close_program_account(vesting_account, destination_account)
+    }
+
+    /// Processes a [CreateMultisig](enum.SplitInstruction.html) instruction
+    pub fn process_create_multisig(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        id: u64,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+        limit: u64,
+        period: Interval,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let creator_account = next_account_info(accounts_iter)?;
+        let multisig_account = next_account_info(accounts_iter)?;
+        let system_account = next_account_info(accounts_iter)?;
+
+        if !creator_account.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        Multisig::validate(&signers, threshold, &period)?;
+
+        let (address, bump) = Multisig::find_address(program_id, creator_account.key, id);
+        if address != *multisig_account.key {
+            msg!("Multisig address should be {}", address);
+            return Err(SplitError::InvalidAccountAddress.into());
+        }
+        Self::create_program_account(
+            program_id,
+            creator_account,
+            multisig_account,
+            system_account,
+            Multisig::LEN,
+            &[
+                MULTISIG_SEED,
+                creator_account.key.as_ref(),
+                &id.to_le_bytes(),
+                &[bump],
+            ],
+        )?;
+
+        Multisig {
+            kind: AccountKind::Multisig,
+            creator: *creator_account.key,
+            id,
+            bump,
+            signers,
+            threshold,
+            limit,
+            period_start: period.now(&Clock::get()?),
+            period,
+            spent: 0,
+            proposal_count: 0,
+        }
+        .save(multisig_account)
+    }
+
+    /// Processes a [MultisigSplit](enum.SplitInstruction.html) instruction
+    pub fn process_multisig_split(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        weights: &Weights,
+        remainder: RemainderPolicy,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let multisig_account = next_account_info(accounts_iter)?;
+        let mut multisig = Multisig::load(multisig_account, program_id)?;
+        if weights.is_empty() || weights.len() > MAX_PAYEES {
+            return Err(SplitError::InvalidPayeeCount.into());
+        }
+        if accounts_iter.len() < weights.len() {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+        let payee_accounts = PayeeAccounts::new(accounts_iter.by_ref().take(weights.len()))?;
+
+        // Each configured signer counts once, however often it is passed
+        let mut signed = vec![false; multisig.signers.len()];
+        for account in accounts_iter.filter(|account| account.is_signer) {
+            if let Some(index) = multisig.signer_index(account.key) {
+                signed[index] = true;
+            }
+        }
+        let approvals = signed.iter().filter(|signed| **signed).count();
+        multisig.roll_period(multisig.period.now(&Clock::get()?));
+        let required = multisig.required_approvals(amount);
+        if approvals < required {
+            msg!("{} of {} required signers signed", approvals, required);
+            return Err(SplitError::NotEnoughSigners.into());
+        }
+
+        let shares = weights.to_shares()?;
+        let (payee_accounts, shares) = Self::distinct_payee_accounts(
+            multisig_account.key,
+            &payee_accounts,
+            &shares,
+            DuplicatePolicy::Reject,
+        )?;
+        let distribution = split::distribute(amount, &shares, remainder)?;
+        Self::transfer_distribution(
+            program_id,
+            accounts,
+            multisig_account,
+            &payee_accounts,
+            &distribution,
+            &[],
+            None,
+            None,
+            None,
+        )?;
+        multisig.record_split(amount, approvals);
+        multisig.save(multisig_account)
+    }
+
+    /// Processes a [CreateProposal](enum.SplitInstruction.html) instruction
+    pub fn process_create_proposal(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+        amount: u64,
+        recipients: Vec<Pubkey>,
+        weights: Weights,
+        remainder: RemainderPolicy,
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let proposer_account = next_account_info(accounts_iter)?;
+        let multisig_account = next_account_info(accounts_iter)?;
+        let proposal_account = next_account_info(accounts_iter)?;
+        let system_account = next_account_info(accounts_iter)?;
+
+        let mut multisig = Multisig::load(multisig_account, program_id)?;
+        if !proposer_account.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let proposer = multisig
+            .signer_index(proposer_account.key)
+            .ok_or(SplitError::NotMultisigSigner)?;
+        SplitGroup::validate(&recipients, &weights)?;
+
+        let index = multisig.proposal_count;
+        let (address, bump) = Proposal::find_address(program_id, multisig_account.key, index);
+        if address != *proposal_account.key {
+            msg!("Proposal address should be {}", address);
+            return Err(SplitError::InvalidAccountAddress.into());
+        }
+        Self::create_program_account(
+            program_id,
+            proposer_account,
+            proposal_account,
+            system_account,
+            Proposal::LEN,
+            &[
+                PROPOSAL_SEED,
+                multisig_account.key.as_ref(),
+                &index.to_le_bytes(),
+                &[bump],
+            ],
+        )?;
+
+        let mut approvals = vec![false; multisig.signers.len()];
+        approvals[proposer] = true;
+        Proposal {
+            kind: AccountKind::Proposal,
+            multisig: *multisig_account.key,
+            proposer: *proposer_account.key,
+            index,
+            amount,
+            remainder,
+            recipients,
+            weights,
+            approvals,
+            executed: false,
+        }
+        .save(proposal_account)?;
+
+        multisig.proposal_count += 1;
+        msg!("created proposal {}", index);
+        multisig.save(multisig_account)
+    }
+
+    /// Processes an [ApproveProposal](enum.SplitInstruction.html) instruction
+    pub fn process_approve_proposal(
+        program_id: &Pubkey,
+        accounts: &[AccountInfo],
+    ) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let signer_account = next_account_info(accounts_iter)?;
+        let multisig_account = next_account_info(accounts_iter)?;
+        let proposal_account = next_account_info(accounts_iter)?;
+
+        let mut multisig = Multisig::load(multisig_account, program_id)?;
+        let mut proposal = Proposal::load(proposal_account, program_id)?;
+        if proposal.multisig != *multisig_account.key {
+            return Err(SplitError::InvalidAccountAddress.into());
+        }
+        if proposal.executed {
+            return Err(SplitError::ProposalExecuted.into());
+        }
+        if !signer_account.is_signer {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let signer = multisig
+            .signer_index(signer_account.key)
+            .ok_or(SplitError::NotMultisigSigner)?;
+
+        multisig.roll_period(multisig.period.now(&Clock::get()?));
+        let required = multisig.required_approvals(proposal.amount);
+        // A signer who already approved may still execute a proposal that
+        // has enough approvals
+        if proposal.approvals[signer] && proposal.approval_count() < required {
+            return Err(SplitError::AlreadyApproved.into());
+        }
+        proposal.approvals[signer] = true;
+        let approvals = proposal.approval_count();
+        msg!("{} of {} required approvals", approvals, required);
+
+        if approvals >= required {
+            // Recipients are checked against the proposal, so a wrong number
+            // of payee accounts is a mismatch
+            let payee_accounts = Self::payee_accounts(accounts_iter)
+                .map_err(|_| ProgramError::from(SplitError::PayeeMismatch))?;
+            Self::check_recipients(&payee_accounts, &proposal.recipients)?;
+            let shares = proposal.weights.to_shares()?;
+            let distribution = split::distribute(proposal.amount, &shares, proposal.remainder)?;
+            Self::transfer_distribution(
+                program_id,
+                accounts,
+                multisig_account,
+                &payee_accounts,
+                &distribution,
+                &[],
+                None,
+                None,
+                None,
+            )?;
+            proposal.executed = true;
+            multisig.record_split(proposal.amount, approvals);
+            multisig.save(multisig_account)?;
+        }
+        proposal.save(proposal_account)
+    }
+
+    /// Processes a [CloseProposal](enum.SplitInstruction.html) instruction
+    pub fn process_close_proposal(program_id: &Pubkey, accounts: &[AccountInfo]) -> ProgramResult {
+        let accounts_iter = &mut accounts.iter();
+        let proposer_account = next_account_info(accounts_iter)?;
+        let proposal_account = next_account_info(accounts_iter)?;
+        let destination_account = next_account_info(accounts_iter)?;
+
+        let proposal = Proposal::load(proposal_account, program_id)?;
+        Self::check_authority(proposer_account, &proposal.proposer)?;
+        if !proposal.executed {
+            msg!("cancelled proposal {}", proposal.index);
+        }
+        Self::close_program_account(proposal_account, destination_account)
+    }
+
+    /// Processes an [Instruction](enum.SplitInstruction.html)
+    pub fn process(program_id: &Pubkey, accounts: &[AccountInfo], input: &[u8]) -> ProgramResult {
+        let instruction = SplitInstruction::unpack(input)?;
+
+        match instruction {
+            SplitInstruction::Legacy { amount } => {
+                msg!("Instruction: Legacy");
+                Self::process_legacy_split(program_id, accounts, amount)
+            }
+            SplitInstruction::Split {
+                amount,
+                remainder,
+                duplicates,
+                dust,
+                split_id,
+                memo,
+            } => {
+                msg!("Instruction: Split");
+                Self::process_split(
+                    program_id,
+                    accounts,
+                    amount,
+                    remainder,
+                    duplicates,
+                    dust,
+                    split_id.as_ref(),
+                    &memo,
+                )
+            }
+            SplitInstruction::WeightedSplit {
+                amount,
+                weights,
+                remainder,
+                duplicates,
+                dust,
+                split_id,
+                memo,
+            } => {
+                msg!("Instruction: WeightedSplit");
+                Self::process_weighted_split(
+                    program_id,
+                    accounts,
+                    amount,
+                    &weights,
+                    remainder,
+                    duplicates,
+                    dust,
+                    split_id.as_ref(),
+                    &memo,
+                )
+            }
+            SplitInstruction::CreateSplitGroup {
+                id,
+                recipients,
+                weights,
+                remainder,
+            } => {
+                msg!("Instruction: CreateSplitGroup");
+                Self::process_create_split_group(
+                    program_id, accounts, id, recipients, weights, remainder,
+                )
+            }
+            SplitInstruction::UpdateSplitGroup {
+                recipients,
+                weights,
+                remainder,
+            } => {
+                msg!("Instruction: UpdateSplitGroup");
+                Self::process_update_split_group(
+                    program_id, accounts, recipients, weights, remainder,
+                )
+            }
+            SplitInstruction::CloseSplitGroup => {
+                msg!("Instruction: CloseSplitGroup");
+                Self::process_close_split_group(program_id, accounts)
+            }
+            SplitInstruction::ExecuteSplitGroup { amount, dust } => {
+                msg!("Instruction: ExecuteSplitGroup");
+                Self::process_execute_split_group(program_id, accounts, amount, dust)
+            }
+            SplitInstruction::CreateDistribution {
+                id,
+                amount,
+                capacity,
+                remainder,
+            } => {
+                msg!("Instruction: CreateDistribution");
+                Self::process_create_distribution(
+                    program_id, accounts, id, amount, capacity, remainder,
+                )
+            }
+            SplitInstruction::AddDistributionRecipients { recipients, shares } => {
+                msg!("Instruction: AddDistributionRecipients");
+                Self::process_add_distribution_recipients(program_id, accounts, recipients, shares)
+            }
+            SplitInstruction::StartDistribution => {
+                msg!("Instruction: StartDistribution");
+                Self::process_start_distribution(program_id, accounts)
+            }
+            SplitInstruction::ContinueDistribution { cursor } => {
+                msg!("Instruction: ContinueDistribution");
+                Self::process_continue_distribution(program_id, accounts, cursor)
+            }
+            SplitInstruction::CloseDistribution => {
+                msg!("Instruction: CloseDistribution");
+                Self::process_close_distribution(program_id, accounts)
+            }
+            SplitInstruction::CreateEscrow { id, capacity } => {
+                msg!("Instruction: CreateEscrow");
+                Self::process_create_escrow(program_id, accounts, id, capacity)
+            }
+            SplitInstruction::DepositToEscrow {
+                amount,
+                payees,
+                weights,
+                remainder,
+            } => {
+                msg!("Instruction: DepositToEscrow");
+                Self::process_deposit_to_escrow(
+                    program_id, accounts, amount, &payees, &weights, remainder,
+                )
+            }
+            SplitInstruction::Withdraw => {
+                msg!("Instruction: Withdraw");
+                Self::process_withdraw(program_id, accounts)
+            }
+            SplitInstruction::CloseEscrow => {
+                msg!("Instruction: CloseEscrow");
+                Self::process_close_escrow(program_id, accounts)
+            }
+            SplitInstruction::TokenSplit {
+                amount,
+                decimals,
+                weights,
+                remainder,
+                duplicates,
+            } => {
+                msg!("Instruction: TokenSplit");
+                Self::process_token_split(
+                    accounts,
+                    amount,
+                    decimals,
+                    weights.as_ref(),
+                    remainder,
+                    duplicates,
+                )
+            }
+            SplitInstruction::CreateSubscription {
+                id,
+                amount,
+                interval,
+                deposit,
+                recipients,
+                weights,
+                remainder,
+            } => {
+                msg!("Instruction: CreateSubscription");
+                Self::process_create_subscription(
+                    program_id, accounts, id, amount, interval, deposit, recipients, weights,
+                    remainder,
+                )
+            }
+            SplitInstruction::Crank => {
+                msg!("Instruction: Crank");
+                Self::process_crank(program_id, accounts)
+            }
+            SplitInstruction::CancelSubscription => {
+                msg!("Instruction: CancelSubscription");
+                Self::process_cancel_subscription(program_id, accounts)
+            }
+            SplitInstruction::RefundSubscription => {
+                msg!("Instruction: RefundSubscription");
+                Self::process_refund_subscription(program_id, accounts)
+            }
+            SplitInstruction::CreateVesting {
+                id,
+                amount,
+                start,
+                cliff,
+                duration,
+                recipients,
+                weights,
+                remainder,
+            } => {
+                msg!("Instruction: CreateVesting");
+                Self::process_create_vesting(
+                    program_id, accounts, id, amount, start, cliff, duration, recipients, &weights,
+                    remainder,
+                )
+            }
+            SplitInstruction::ClaimVested => {
+                msg!("Instruction: ClaimVested");
+                Self::process_claim_vested(program_id, accounts)
+            }
+            SplitInstruction::RevokeVesting => {
+                msg!("Instruction: RevokeVesting");
+                Self::process_revoke_vesting(program_id, accounts)
+            }
+            SplitInstruction::CloseVesting => {
+                msg!("Instruction: CloseVesting");
+                Self::process_close_vesting(program_id, accounts)
+            }
+            SplitInstruction::CreateMultisig {
+                id,
+                signers,
+                threshold,
+                limit,
+                period,
+            } => {
+                msg!("Instruction: CreateMultisig");
+                Self::process_create_multisig(
+                    program_id, accounts, id, signers, threshold, limit, period,
+                )
+            }
+            SplitInstruction::MultisigSplit {
+                amount,
+                weights,
+                remainder,
+            } => {
+                msg!("Instruction: MultisigSplit");
+                Self::process_multisig_split(program_id, accounts, amount, &weights, remainder)
+            }
+            SplitInstruction::CreateProposal {
+                amount,
+                recipients,
+                weights,
+                remainder,
+            } => {
+                msg!("Instruction: CreateProposal");
+                Self::process_create_proposal(
+                    program_id, accounts, amount, recipients, weights, remainder,
+                )
+            }
+            SplitInstruction::ApproveProposal => {
+                msg!("Instruction: ApproveProposal");
+                Self::process_approve_proposal(program_id, accounts)
+            }
+            SplitInstruction::VaultSplit {
+                amount,
+                weights,
+                remainder,
+                duplicates,
+                dust,
+            } => {
+                msg!("Instruction: VaultSplit");
+                Self::process_vault_split(
+                    program_id, accounts, amount, &weights, remainder, duplicates, dust,
+                )
+            }
+            SplitInstruction::CreateReceiptLog { capacity } => {
+                msg!("Instruction: CreateReceiptLog");
+                Self::process_create_receipt_log(program_id, accounts, capacity)
+            }
+            SplitInstruction::CloseReceiptLog => {
+                msg!("Instruction: CloseReceiptLog");
+                Self::process_close_receipt_log(program_id, accounts)
+            }
+            SplitInstruction::CreatePayeeLedger { payee } => {
+                msg!("Instruction: CreatePayeeLedger");
+                Self::process_create_payee_ledger(program_id, accounts, &payee)
+            }
+            SplitInstruction::ClosePayeeLedger => {
+                msg!("Instruction: ClosePayeeLedger");
+                Self::process_close_payee_ledger(program_id, accounts)
+            }
+            SplitInstruction::Quote {
+                amount,
+                weights,
+                remainder,
+                duplicates,
+                dust,
+            } => {
+                msg!("Instruction: Quote");
+                Self::process_quote(
+                    accounts,
+                    amount,
+                    weights.as_ref(),
+                    remainder,
+                    duplicates,
+                    dust,
+                )
+            }
+            SplitInstruction::CloseProposal => {
+                msg!("Instruction: CloseProposal");
+                Self::process_close_proposal(program_id, accounts)
+            }
+            SplitInstruction::CancelDistribution => {
+                msg!("Instruction: CancelDistribution");
+                Self::process_cancel_distribution(program_id, accounts)
+            }
+        }
+    }
+}
@@ -0,0 +1,630 @@
+//! Split math shared by every distribution instruction
+
+use crate::error::SplitError;
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo, msg, program_error::ProgramError, pubkey::Pubkey, system_program,
+};
+
+/// Maximum number of payees that can be paid in a single instruction
+pub const MAX_PAYEES: usize = 10;
+
+/// Basis points that make up a whole amount
+pub const BASIS_POINTS_TOTAL: u64 = 10_000;
+
+/// Longest memo a split accepts, in bytes
+pub const MAX_MEMO_LEN: usize = 256;
+
+/// Anything a payee's address can be read from, so the accounts passed to
+/// an instruction can be planned for without copying out their keys
+pub trait PayeeKey {
+    /// Address of the payee
+    fn payee_key(&self) -> &Pubkey;
+}
+
+impl PayeeKey for Pubkey {
+    fn payee_key(&self) -> &Pubkey {
+        self
+    }
+}
+
+impl PayeeKey for AccountInfo<'_> {
+    fn payee_key(&self) -> &Pubkey {
+        self.key
+    }
+}
+
+impl<P: PayeeKey + ?Sized> PayeeKey for &P {
+    fn payee_key(&self) -> &Pubkey {
+        (**self).payee_key()
+    }
+}
+
+/// Relative weight of each payee, in the same order as the payee accounts
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub enum Weights {
+    /// Basis points per payee, which must add up to `BASIS_POINTS_TOTAL`
+    BasisPoints(Vec<u16>),
+    /// Arbitrary shares per payee, each payee receives `share / sum(shares)`
+    Shares(Vec<u64>),
+}
+
+impl Weights {
+    /// Number of payees the weights describe
+    pub fn len(&self) -> usize {
+        match self {
+            Self::BasisPoints(points) => points.len(),
+            Self::Shares(shares) => shares.len(),
+        }
+    }
+
+    /// Returns true if no payee is weighted
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Checks that the weights add up and returns them as plain shares
+    pub fn to_shares(&self) -> Result<Vec<u64>, ProgramError> {
+        let shares: Vec<u64> = match self {
+            Self::BasisPoints(points) => points.iter().map(|point| u64::from(*point)).collect(),
+            Self::Shares(shares) => shares.clone(),
+        };
+        let total: u128 = shares.iter().map(|share| u128::from(*share)).sum();
+        match self {
+            Self::BasisPoints(_) if total != u128::from(BASIS_POINTS_TOTAL) => {
+                msg!(
+                    "Basis points add up to {}, expected {}",
+                    total,
+                    BASIS_POINTS_TOTAL
+                );
+                Err(SplitError::InvalidBasisPoints.into())
+            }
+            _ if total == 0 => Err(SplitError::ZeroWeights.into()),
+            _ => Ok(shares),
+        }
+    }
+}
+
+/// How the lamports left over after rounding every amount down are handled
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum RemainderPolicy {
+    /// The remainder is never transferred and stays with the payer
+    Payer,
+    /// The first payee with a non-zero weight receives the remainder
+    FirstPayee,
+    /// The last payee with a non-zero weight receives the remainder
+    LastPayee,
+    /// The remainder is spread one lamport at a time to the payees whose
+    /// exact amounts were rounded down the most
+    LargestRemainder,
+    /// The split fails unless the amount divides exactly
+    RejectUneven,
+}
+
+impl RemainderPolicy {
+    /// Describes where the remainder went, for the program log
+    pub fn description(&self) -> &'static str {
+        match self {
+            Self::Payer => "left with the payer",
+            Self::FirstPayee => "given to the first payee",
+            Self::LastPayee => "given to the last payee",
+            Self::LargestRemainder => "spread by largest remainder",
+            Self::RejectUneven => "rejected unless zero",
+        }
+    }
+}
+
+/// How a payee listed more than once is handled
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum DuplicatePolicy {
+    /// The split fails
+    Reject,
+    /// The payee is paid once, with the weights of every listing added together
+    Merge,
+}
+
+/// Where the memo carried by a split comes from
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub enum MemoPolicy {
+    /// The split carries no memo
+    None,
+    /// The memo is given in the instruction data
+    Inline(String),
+    /// The transaction must include an SPL Memo instruction, found through
+    /// the instructions sysvar
+    Transaction,
+}
+
+/// Checks a memo is non-empty and at most `MAX_MEMO_LEN` bytes
+pub fn check_memo(memo: &str) -> Result<(), ProgramError> {
+    if memo.is_empty() || memo.len() > MAX_MEMO_LEN {
+        msg!("Memo is {} bytes, max is {}", memo.len(), MAX_MEMO_LEN);
+        return Err(SplitError::InvalidMemo.into());
+    }
+    Ok(())
+}
+
+/// Payees left after checking for duplicates, with their shares
+#[derive(Clone, Debug, PartialEq)]
+pub struct DistinctPayees {
+    /// Position of each distinct payee in the original list
+    pub indices: Vec<usize>,
+    /// Share of each distinct payee
+    pub shares: Vec<u64>,
+}
+
+/// Checks that neither the payer, when known, nor the system program is
+/// listed as a payee, and handles payees listed more than once according to
+/// `policy`. Merged payees keep the position of their first listing.
+pub fn distinct_payees<P: PayeeKey>(
+    payer: Option<&Pubkey>,
+    payees: &[P],
+    shares: &[u64],
+    policy: DuplicatePolicy,
+) -> Result<DistinctPayees, ProgramError> {
+    let mut distinct = DistinctPayees {
+        indices: Vec::with_capacity(payees.len()),
+        shares: Vec::with_capacity(payees.len()),
+    };
+    for (index, (payee, share)) in payees.iter().zip(shares).enumerate() {
+        let payee = payee.payee_key();
+        if Some(payee) == payer {
+            msg!("Payer {} is listed as a payee", payee);
+            return Err(SplitError::PayerIsPayee.into());
+        }
+        if *payee == system_program::id() {
+            msg!("System program is listed as a payee");
+            return Err(SplitError::SystemProgramIsPayee.into());
+        }
+        let first = distinct
+            .indices
+            .iter()
+            .position(|distinct_index| payees[*distinct_index].payee_key() == payee);
+        match (first, policy) {
+            (None, _) => {
+                distinct.indices.push(index);
+                distinct.shares.push(*share);
+            }
+            (Some(_), DuplicatePolicy::Reject) => {
+                msg!("Payee {} is listed more than once", payee);
+                return Err(SplitError::DuplicatePayee.into());
+            }
+            (Some(position), DuplicatePolicy::Merge) => {
+                msg!("merged duplicate payee {}", payee);
+                distinct.shares[position] = distinct.shares[position]
+                    .checked_add(*share)
+                    .ok_or(SplitError::Overflow)?;
+            }
+        }
+    }
+    Ok(distinct)
+}
+
+/// How a share too small to leave its payee rent-exempt is handled
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum DustPolicy {
+    /// The share is paid anyway
+    Allow,
+    /// The share isn't paid and stays with the payer
+    Refund,
+    /// The payee is dropped and the amount is split between the others
+    Redistribute,
+    /// The split fails
+    Reject,
+}
+
+impl DustPolicy {
+    /// Describes what happened to a dust share, for logging
+    pub fn description(&self) -> &'static str {
+        match self {
+            DustPolicy::Allow => "paid anyway",
+            DustPolicy::Refund => "refunded to payer",
+            DustPolicy::Redistribute => "redistributed to other payees",
+            DustPolicy::Reject => "rejected",
+        }
+    }
+}
+
+/// A share that would leave its payee below the rent-exempt minimum
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DustShare {
+    /// Position of the payee
+    pub index: usize,
+    /// Lamports the payee would have received
+    pub amount: u64,
+}
+
+/// Lamports each payee receives after applying a remainder policy
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distribution {
+    /// Amount for each payee, in payee order
+    pub amounts: Vec<u64>,
+    /// Lamports left over after rounding every amount down
+    pub remainder: u64,
+    /// Policy that decided where `remainder` went
+    pub policy: RemainderPolicy,
+}
+
+impl Distribution {
+    /// Total lamports transferred to payees
+    pub fn total(&self) -> u64 {
+        self.amounts.iter().sum()
+    }
+}
+
+/// Splits `amount` in proportion to `shares`, rounding each amount down.
+///
+/// Intermediate products are computed as u128 so `amount * share` can't
+/// overflow. The rounded-down amounts never add up to more than `amount`.
+pub fn weighted_amounts(amount: u64, shares: &[u64]) -> Result<Vec<u64>, ProgramError> {
+    let total: u128 = shares.iter().map(|share| u128::from(*share)).sum();
+    if total == 0 {
+        return Err(SplitError::ZeroWeights.into());
+    }
+    Ok(shares
+        .iter()
+        .map(|share| (u128::from(amount) * u128::from(*share) / total) as u64)
+        .collect())
+}
+
+/// Splits `amount` in proportion to `shares` and hands out the remainder
+/// according to `policy`
+pub fn distribute(
+    amount: u64,
+    shares: &[u64],
+    policy: RemainderPolicy,
+) -> Result<Distribution, ProgramError> {
+    let mut amounts = weighted_amounts(amount, shares)?;
+    let remainder = amount - amounts.iter().sum::<u64>();
+
+    if remainder > 0 {
+        match policy {
+            RemainderPolicy::Payer => {}
+            // A remainder is only left when some share is non-zero, and a
+            // payee weighted zero is never paid
+            RemainderPolicy::FirstPayee => {
+                if let Some(first) = shares.iter().position(|share| *share > 0) {
+                    amounts[first] += remainder;
+                }
+            }
+            RemainderPolicy::LastPayee => {
+                if let Some(last) = shares.iter().rposition(|share| *share > 0) {
+                    amounts[last] += remainder;
+                }
+            }
+            RemainderPolicy::LargestRemainder => {
+                // The remainder is always smaller than the number of payees,
+                // so each payee gets at most one extra lamport
+                let total: u128 = shares.iter().map(|share| u128::from(*share)).sum();
+                let mut order: Vec<usize> = (0..shares.len()).collect();
+                order.sort_by_key(|i| {
+                    std::cmp::Reverse(u128::from(amount) * u128::from(shares[*i]) % total)
+                });
+                for i in order.into_iter().take(remainder as usize) {
+                    amounts[i] += 1;
+                }
+            }
+            RemainderPolicy::RejectUneven => {
+                msg!(
+                    "{} lamports don't divide evenly, {} left over",
+                    amount,
+                    remainder
+                );
+                return Err(SplitError::UnevenSplit.into());
+            }
+        }
+    }
+
+    Ok(Distribution {
+        amounts,
+        remainder,
+        policy,
+    })
+}
+
+/// Logs each dust share with what happened to it
+fn log_dust<P: PayeeKey>(
+    payees: &[P],
+    affected: impl Iterator<Item = DustShare>,
+    dust: DustPolicy,
+) {
+    for share in affected {
+        msg!(
+            "dust share of {} lamports for {} {}",
+            share.amount,
+            payees[share.index].payee_key(),
+            dust.description()
+        );
+    }
+}
+
+/// Splits `amount` like `distribute`, then applies `dust` to every share
+/// smaller than its payee's shortfall, the lamports it needs to become
+/// rent-exempt. Every affected payee is logged, and returned with the
+/// distribution.
+pub fn distribute_without_dust<P: PayeeKey>(
+    amount: u64,
+    payees: &[P],
+    shares: &[u64],
+    remainder: RemainderPolicy,
+    shortfalls: &[u64],
+    dust: DustPolicy,
+) -> Result<(Distribution, Vec<DustShare>), ProgramError> {
+    apply_dust_policy(amount, shares, remainder, shortfalls, dust, |affected| {
+        log_dust(payees, affected.iter().copied(), dust)
+    })
+}
+
+/// Does the work of `distribute_without_dust`, handing the affected shares
+/// to `log` instead of logging them against a payee list
+fn apply_dust_policy(
+    amount: u64,
+    shares: &[u64],
+    remainder: RemainderPolicy,
+    shortfalls: &[u64],
+    dust: DustPolicy,
+    log: impl Fn(&[DustShare]),
+) -> Result<(Distribution, Vec<DustShare>), ProgramError> {
+    let is_dust = |index: usize, share: u64| share > 0 && share < shortfalls[index];
+    let mut distribution = distribute(amount, shares, remainder)?;
+    let mut affected: Vec<DustShare> = distribution
+        .amounts
+        .iter()
+        .enumerate()
+        .filter(|(index, share)| is_dust(*index, **share))
+        .map(|(index, share)| DustShare {
+            index,
+            amount: *share,
+        })
+        .collect();
+    if affected.is_empty() {
+        return Ok((distribution, affected));
+    }
+
+    match dust {
+        DustPolicy::Allow => {}
+        DustPolicy::Refund => {
+            for share in affected.iter() {
+                distribution.amounts[share.index] = 0;
+            }
+        }
+        DustPolicy::Redistribute => {
+            // Dropping payees raises everyone else's share, so repeat until
+            // no remaining payee is paid dust
+            let mut eligible: Vec<usize> = (0..shares.len())
+                .filter(|index| affected.iter().all(|share| share.index != *index))
+                .collect();
+            loop {
+                let eligible_shares: Vec<u64> =
+                    eligible.iter().map(|index| shares[*index]).collect();
+                if eligible_shares.iter().all(|share| *share == 0) {
+                    log(&affected);
+                    msg!("Every payee share is below the rent-exempt minimum");
+                    return Err(SplitError::DustShare.into());
+                }
+                let subset = distribute(amount, &eligible_shares, remainder)?;
+                let dropped: Vec<DustShare> = eligible
+                    .iter()
+                    .zip(&subset.amounts)
+                    .filter(|(index, share)| is_dust(**index, **share))
+                    .map(|(index, share)| DustShare {
+                        index: *index,
+                        amount: *share,
+                    })
+                    .collect();
+                if dropped.is_empty() {
+                    let mut amounts = vec![0; shares.len()];
+                    for (index, share) in eligible.iter().zip(&subset.amounts) {
+                        amounts[*index] = *share;
+                    }
+                    distribution = Distribution { amounts, ..subset };
+                    break;
+                }
+                eligible.retain(|index| dropped.iter().all(|share| share.index != *index));
+                affected.extend(dropped);
+            }
+        }
+        DustPolicy::Reject => {}
+    }
+    log(&affected);
+    if dust == DustPolicy::Reject {
+        return Err(SplitError::DustShare.into());
+    }
+    Ok((distribution, affected))
+}
+
+/// Payments a split would make, worked out without moving any lamports
+#[derive(Clone, Debug, PartialEq)]
+pub struct Plan {
+    /// Position of each payee paid, in the original payee list
+    pub indices: Vec<usize>,
+    /// Amount for each payee in `indices`, with the remainder
+    pub distribution: Distribution,
+    /// Dust shares found, indexed into the original payee list
+    pub dust: Vec<DustShare>,
+}
+
+/// Plans a split of `amount` from `payer` the way `WeightedSplit` pays it:
+/// weights are checked, duplicates handled by `duplicates`, the remainder
+/// by `remainder` and dust by `dust`. `shortfalls` holds the lamports each
+/// payee needs to become rent-exempt, zero to ignore rent. Off-chain callers
+/// get the same numbers the program would pay.
+#[allow(clippy::too_many_arguments)]
+pub fn plan_distribution<P: PayeeKey>(
+    payer: &Pubkey,
+    payees: &[P],
+    amount: u64,
+    weights: &Weights,
+    remainder: RemainderPolicy,
+    duplicates: DuplicatePolicy,
+    shortfalls: &[u64],
+    dust: DustPolicy,
+) -> Result<Plan, ProgramError> {
+    if weights.len() != payees.len() || shortfalls.len() != payees.len() {
+        msg!(
+            "Got {} weights for {} payee accounts",
+            weights.len(),
+            payees.len()
+        );
+        return Err(SplitError::WeightCountMismatch.into());
+    }
+    let shares = weights.to_shares()?;
+    let distinct = distinct_payees(Some(payer), payees, &shares, duplicates)?;
+    let distinct_shortfalls: Vec<u64> = distinct.indices.iter().map(|i| shortfalls[*i]).collect();
+    // Dust is found among the distinct payees, so map it back to positions
+    // in `payees` before it is logged or returned
+    let original = |share: &DustShare| DustShare {
+        index: distinct.indices[share.index],
+        amount: share.amount,
+    };
+    let (distribution, dust) = apply_dust_policy(
+        amount,
+        &distinct.shares,
+        remainder,
+        &distinct_shortfalls,
+        dust,
+        |affected| log_dust(payees, affected.iter().map(original), dust),
+    )?;
+    let dust = dust.iter().map(original).collect();
+    Ok(Plan {
+        indices: distinct.indices,
+        distribution,
+        dust,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weighted_amounts() {
+        assert_eq!(weighted_amounts(10, &[1, 2, 3]).unwrap(), vec![1, 3, 5]);
+        // `amount * share` would overflow a u64
+        assert_eq!(
+            weighted_amounts(u64::MAX, &[u64::MAX, u64::MAX]).unwrap(),
+            vec![u64::MAX / 2, u64::MAX / 2]
+        );
+        assert_eq!(
+            weighted_amounts(10, &[0, 0]).unwrap_err(),
+            SplitError::ZeroWeights.into()
+        );
+    }
+
+    #[test]
+    fn test_distribute() {
+        let amounts = |policy| distribute(10, &[1, 1, 1], policy).unwrap().amounts;
+        assert_eq!(amounts(RemainderPolicy::Payer), vec![3, 3, 3]);
+        assert_eq!(amounts(RemainderPolicy::FirstPayee), vec![4, 3, 3]);
+        assert_eq!(amounts(RemainderPolicy::LastPayee), vec![3, 3, 4]);
+
+        // 10 * [1, 2, 3] / 6 rounds down to [1, 3, 5], and the first payee
+        // lost the most to rounding
+        let distribution = distribute(10, &[1, 2, 3], RemainderPolicy::LargestRemainder).unwrap();
+        assert_eq!(distribution.amounts, vec![2, 3, 5]);
+        assert_eq!(distribution.remainder, 1);
+        assert_eq!(distribution.total(), 10);
+
+        assert_eq!(
+            distribute(10, &[1, 1, 1], RemainderPolicy::RejectUneven).unwrap_err(),
+            SplitError::UnevenSplit.into()
+        );
+        assert_eq!(
+            distribute(9, &[1, 1, 1], RemainderPolicy::RejectUneven)
+                .unwrap()
+                .amounts,
+            vec![3, 3, 3]
+        );
+    }
+
+    #[test]
+    fn test_apply_dust_policy() {
+        // The last payee needs 150 lamports to become rent-exempt
+        let apply = |dust| {
+            apply_dust_policy(
+                300,
+                &[1, 1, 1],
+                RemainderPolicy::Payer,
+                &[0, 0, 150],
+                dust,
+                |_| {},
+            )
+        };
+
+        let (distribution, affected) = apply(DustPolicy::Allow).unwrap();
+        assert_eq!(distribution.amounts, vec![100, 100, 100]);
+        assert_eq!(
+            affected,
+            vec![DustShare {
+                index: 2,
+                amount: 100
+            }]
+        );
+
+        let (distribution, _) = apply(DustPolicy::Refund).unwrap();
+        assert_eq!(distribution.amounts, vec![100, 100, 0]);
+
+        let (distribution, _) = apply(DustPolicy::Redistribute).unwrap();
+        assert_eq!(distribution.amounts, vec![150, 150, 0]);
+
+        assert_eq!(
+            apply(DustPolicy::Reject).unwrap_err(),
+            SplitError::DustShare.into()
+        );
+
+        // Redistributing fails once every payee has been dropped
+        assert_eq!(
+            apply_dust_policy(
+                300,
+                &[1, 1],
+                RemainderPolicy::Payer,
+                &[200, 200],
+                DustPolicy::Redistribute,
+                |_| {}
+            )
+            .unwrap_err(),
+            SplitError::DustShare.into()
+        );
+    }
+
+    #[test]
+    fn test_plan_distribution() {
+        let payer = Pubkey::new_unique();
+        let payee = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let plan = |payees: &[Pubkey], weights: Weights| {
+            plan_distribution(
+                &payer,
+                payees,
+                1_000,
+                &weights,
+                RemainderPolicy::Payer,
+                DuplicatePolicy::Merge,
+                &vec![0; payees.len()],
+                DustPolicy::Allow,
+            )
+        };
+
+        // Merged payees keep the position of their first listing
+        let merged = plan(
+            &[payee, other, payee],
+            Weights::BasisPoints(vec![2_500, 5_000, 2_500]),
+        )
+        .unwrap();
+        assert_eq!(merged.indices, vec![0, 1]);
+        assert_eq!(merged.distribution.amounts, vec![500, 500]);
+
+        assert_eq!(
+            plan(&[payee, payer], Weights::Shares(vec![1, 1])).unwrap_err(),
+            SplitError::PayerIsPayee.into()
+        );
+        assert_eq!(
+            plan(&[payee, system_program::id()], Weights::Shares(vec![1, 1])).unwrap_err(),
+            SplitError::SystemProgramIsPayee.into()
+        );
+        assert_eq!(
+            plan(&[payee], Weights::BasisPoints(vec![9_999])).unwrap_err(),
+            SplitError::InvalidBasisPoints.into()
+        );
+    }
+}
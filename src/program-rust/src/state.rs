@@ -0,0 +1,940 @@
+//! State transition types
+
+use crate::{
+    error::SplitError,
+    split::{self, DuplicatePolicy, RemainderPolicy, Weights, MAX_PAYEES},
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    account_info::AccountInfo,
+    borsh::try_from_slice_unchecked,
+    clock::Clock,
+    hash::{hashv, Hash},
+    msg,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+};
+
+/// Seed prefix for split group addresses
+pub const SPLIT_GROUP_SEED: &[u8] = b"split_group";
+
+/// Seed prefix for paged distribution addresses
+pub const DISTRIBUTION_SEED: &[u8] = b"distribution";
+
+/// Most recipients a paged distribution account can hold
+pub const MAX_DISTRIBUTION_RECIPIENTS: usize = 200;
+
+/// Seed prefix for escrow addresses
+pub const ESCROW_SEED: &[u8] = b"escrow";
+
+/// Most payees an escrow account can hold entitlements for
+pub const MAX_ESCROW_PAYEES: usize = 200;
+
+/// Seed prefix for subscription addresses
+pub const SUBSCRIPTION_SEED: &[u8] = b"subscription";
+
+/// Seed prefix for vesting addresses
+pub const VESTING_SEED: &[u8] = b"vesting";
+
+/// Seed prefix for multisig vault addresses
+pub const MULTISIG_SEED: &[u8] = b"multisig";
+
+/// Seed prefix for multisig proposal addresses
+pub const PROPOSAL_SEED: &[u8] = b"proposal";
+
+/// Most signers a multisig can be configured with
+pub const MAX_MULTISIG_SIGNERS: usize = 11;
+
+/// Seed prefix for vault addresses
+pub const VAULT_SEED: &[u8] = b"vault";
+
+/// Seed prefix for receipt log addresses
+pub const RECEIPT_LOG_SEED: &[u8] = b"receipts";
+
+/// Most receipts a receipt log can hold before overwriting the oldest
+pub const MAX_RECEIPTS: usize = 20;
+
+/// Seed prefix for split record addresses
+pub const SPLIT_RECORD_SEED: &[u8] = b"split_id";
+
+/// Seed prefix for payee ledger addresses
+pub const LEDGER_SEED: &[u8] = b"ledger";
+
+/// Finds the program address of `authority`'s vault. The vault is a plain
+/// system account funded by transfers, which the program signs for when it
+/// is the source of a split.
+pub fn find_vault_address(program_id: &Pubkey, authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[VAULT_SEED, authority.as_ref()], program_id)
+}
+
+/// Kind of state held by a program-owned account, stored as its first byte
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum AccountKind {
+    /// Account has not been initialized
+    Uninitialized,
+    /// A `SplitGroup`
+    SplitGroup,
+    /// A `PagedDistribution`
+    PagedDistribution,
+    /// An `Escrow`
+    Escrow,
+    /// A `Subscription`
+    Subscription,
+    /// A `Vesting` schedule
+    Vesting,
+    /// A `Multisig` vault
+    Multisig,
+    /// A `Proposal` awaiting multisig approval
+    Proposal,
+    /// A `ReceiptLog`
+    ReceiptLog,
+    /// A `PayeeLedger`
+    PayeeLedger,
+    /// A `SplitRecord`
+    SplitRecord,
+}
+
+/// State stored in a program-owned account
+pub trait ProgramAccount: BorshSerialize + BorshDeserialize {
+    /// Kind stored in the account's first byte
+    const KIND: AccountKind;
+
+    /// Size of the account data, large enough for the biggest instance
+    const LEN: usize;
+
+    /// Returns the kind recorded in the instance
+    fn kind(&self) -> AccountKind;
+
+    /// Loads state from an account owned by `program_id`
+    fn load(account: &AccountInfo, program_id: &Pubkey) -> Result<Self, ProgramError> {
+        if account.owner != program_id {
+            msg!("Account {} is not owned by the program", account.key);
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        let state: Self = try_from_slice_unchecked(&account.data.borrow())
+            .map_err(|_| ProgramError::InvalidAccountData)?;
+        if state.kind() != Self::KIND {
+            msg!("Account {} does not hold a {:?}", account.key, Self::KIND);
+            return Err(SplitError::UnexpectedAccountKind.into());
+        }
+        Ok(state)
+    }
+
+    /// Writes state to the start of the account data
+    fn save(&self, account: &AccountInfo) -> Result<(), ProgramError> {
+        let data = self.try_to_vec()?;
+        let mut account_data = account.try_borrow_mut_data()?;
+        if data.len() > account_data.len() {
+            return Err(ProgramError::AccountDataTooSmall);
+        }
+        account_data[..data.len()].copy_from_slice(&data);
+        Ok(())
+    }
+}
+
+/// A stored list of recipients and weights that can be paid repeatedly
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct SplitGroup {
+    /// Always `AccountKind::SplitGroup`
+    pub kind: AccountKind,
+    /// Key allowed to update or close the group
+    pub authority: Pubkey,
+    /// Identifier distinguishing the authority's groups, part of the address seeds
+    pub id: u64,
+    /// Bump seed of the group's program address
+    pub bump: u8,
+    /// Where lamports left over after rounding go
+    pub remainder: RemainderPolicy,
+    /// Recipient addresses, in payment order
+    pub recipients: Vec<Pubkey>,
+    /// One weight per recipient
+    pub weights: Weights,
+}
+
+impl SplitGroup {
+    /// Finds the program address of `authority`'s group `id`
+    pub fn find_address(program_id: &Pubkey, authority: &Pubkey, id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[SPLIT_GROUP_SEED, authority.as_ref(), &id.to_le_bytes()],
+            program_id,
+        )
+    }
+
+    /// Checks the recipients and weights describe a valid split
+    pub fn validate(recipients: &[Pubkey], weights: &Weights) -> Result<(), ProgramError> {
+        if recipients.is_empty() || recipients.len() > MAX_PAYEES {
+            msg!(
+                "Split group has {} recipients, max is {}",
+                recipients.len(),
+                MAX_PAYEES
+            );
+            return Err(SplitError::InvalidPayeeCount.into());
+        }
+        if weights.len() != recipients.len() {
+            return Err(SplitError::WeightCountMismatch.into());
+        }
+        let shares = weights.to_shares()?;
+        split::distinct_payees(None, recipients, &shares, DuplicatePolicy::Reject).map(|_| ())
+    }
+}
+
+impl ProgramAccount for SplitGroup {
+    const KIND: AccountKind = AccountKind::SplitGroup;
+
+    // kind + authority + id + bump + remainder + recipients + weights as shares
+    const LEN: usize = 1 + 32 + 8 + 1 + 1 + (4 + 32 * MAX_PAYEES) + (1 + 4 + 8 * MAX_PAYEES);
+
+    fn kind(&self) -> AccountKind {
+        self.kind
+    }
+}
+
+/// Progress of a paged distribution
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum DistributionStatus {
+    /// Recipients are still being added and no lamports are held
+    Open,
+    /// Funded, pages are being paid out
+    Active,
+    /// Every recipient has been paid
+    Complete,
+}
+
+/// A recipient of a paged distribution
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct DistributionRecipient {
+    /// Address credited with `amount`
+    pub address: Pubkey,
+    /// Relative weight of the recipient
+    pub share: u64,
+    /// Lamports owed, set when the distribution starts
+    pub amount: u64,
+}
+
+/// A distribution to more recipients than fit in one transaction. The
+/// account holds the lamports being distributed and pays them out a page
+/// at a time, in recipient order.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct PagedDistribution {
+    /// Always `AccountKind::PagedDistribution`
+    pub kind: AccountKind,
+    /// Key allowed to add recipients, start and close the distribution
+    pub authority: Pubkey,
+    /// Identifier distinguishing the authority's distributions, part of the address seeds
+    pub id: u64,
+    /// Bump seed of the distribution's program address
+    pub bump: u8,
+    /// Total lamports to distribute
+    pub amount: u64,
+    /// Where lamports left over after rounding go
+    pub remainder: RemainderPolicy,
+    /// Progress of the distribution
+    pub status: DistributionStatus,
+    /// Index of the next recipient to pay
+    pub cursor: u32,
+    /// Recipients, in payment order
+    pub recipients: Vec<DistributionRecipient>,
+}
+
+impl PagedDistribution {
+    // kind + authority + id + bump + amount + remainder + status + cursor + vec length
+    const HEADER_LEN: usize = 1 + 32 + 8 + 1 + 8 + 1 + 1 + 4 + 4;
+
+    // address + share + amount
+    const RECIPIENT_LEN: usize = 32 + 8 + 8;
+
+    /// Size of a distribution account that holds up to `capacity` recipients
+    pub fn space(capacity: usize) -> usize {
+        Self::HEADER_LEN + Self::RECIPIENT_LEN * capacity
+    }
+
+    /// Number of recipients the account can hold
+    pub fn capacity(data_len: usize) -> usize {
+        data_len.saturating_sub(Self::HEADER_LEN) / Self::RECIPIENT_LEN
+    }
+
+    /// Finds the program address of `authority`'s distribution `id`
+    pub fn find_address(program_id: &Pubkey, authority: &Pubkey, id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[DISTRIBUTION_SEED, authority.as_ref(), &id.to_le_bytes()],
+            program_id,
+        )
+    }
+}
+
+impl ProgramAccount for PagedDistribution {
+    const KIND: AccountKind = AccountKind::PagedDistribution;
+
+    const LEN: usize = Self::HEADER_LEN + Self::RECIPIENT_LEN * MAX_DISTRIBUTION_RECIPIENTS;
+
+    fn kind(&self) -> AccountKind {
+        self.kind
+    }
+}
+
+/// Lamports a payee may withdraw from an escrow
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct Entitlement {
+    /// Payee allowed to withdraw
+    pub payee: Pubkey,
+    /// Lamports deposited for the payee so far
+    pub amount: u64,
+    /// Lamports the payee has withdrawn so far
+    pub withdrawn: u64,
+}
+
+impl Entitlement {
+    /// Lamports the payee can withdraw now
+    pub fn outstanding(&self) -> u64 {
+        self.amount - self.withdrawn
+    }
+}
+
+/// A vault holding deposited lamports until each payee withdraws their share
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct Escrow {
+    /// Always `AccountKind::Escrow`
+    pub kind: AccountKind,
+    /// Key allowed to deposit into and close the escrow
+    pub depositor: Pubkey,
+    /// Identifier distinguishing the depositor's escrows, part of the address seeds
+    pub id: u64,
+    /// Bump seed of the escrow's program address
+    pub bump: u8,
+    /// Entitlement of each payee, one entry per payee
+    pub entitlements: Vec<Entitlement>,
+}
+
+impl Escrow {
+    // kind + depositor + id + bump + vec length
+    const HEADER_LEN: usize = 1 + 32 + 8 + 1 + 4;
+
+    // payee + amount + withdrawn
+    const ENTITLEMENT_LEN: usize = 32 + 8 + 8;
+
+    /// Size of an escrow account that holds up to `capacity` payees
+    pub fn space(capacity: usize) -> usize {
+        Self::HEADER_LEN + Self::ENTITLEMENT_LEN * capacity
+    }
+
+    /// Number of payees the account can hold
+    pub fn capacity(data_len: usize) -> usize {
+        data_len.saturating_sub(Self::HEADER_LEN) / Self::ENTITLEMENT_LEN
+    }
+
+    /// Finds the program address of `depositor`'s escrow `id`
+    pub fn find_address(program_id: &Pubkey, depositor: &Pubkey, id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[ESCROW_SEED, depositor.as_ref(), &id.to_le_bytes()],
+            program_id,
+        )
+    }
+
+    /// Credits `amount` to `payee`, adding an entry if the payee is new
+    pub fn credit(&mut self, payee: &Pubkey, amount: u64) -> Result<(), ProgramError> {
+        match self
+            .entitlements
+            .iter_mut()
+            .find(|entitlement| entitlement.payee == *payee)
+        {
+            Some(entitlement) => {
+                entitlement.amount = entitlement
+                    .amount
+                    .checked_add(amount)
+                    .ok_or(SplitError::Overflow)?;
+            }
+            None => self.entitlements.push(Entitlement {
+                payee: *payee,
+                amount,
+                withdrawn: 0,
+            }),
+        }
+        Ok(())
+    }
+
+    /// Lamports owed to payees that haven't been withdrawn yet
+    pub fn outstanding(&self) -> u64 {
+        self.entitlements
+            .iter()
+            .map(|entitlement| entitlement.outstanding())
+            .sum()
+    }
+}
+
+impl ProgramAccount for Escrow {
+    const KIND: AccountKind = AccountKind::Escrow;
+
+    const LEN: usize = Self::HEADER_LEN + Self::ENTITLEMENT_LEN * MAX_ESCROW_PAYEES;
+
+    fn kind(&self) -> AccountKind {
+        self.kind
+    }
+}
+
+/// Time between subscription payments, measured with the `Clock` sysvar
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum Interval {
+    /// A number of slots
+    Slots(u64),
+    /// A number of seconds of unix time
+    Seconds(u64),
+}
+
+impl Interval {
+    /// Length of one period, in the interval's unit
+    pub fn length(&self) -> u64 {
+        match self {
+            Interval::Slots(slots) => *slots,
+            Interval::Seconds(seconds) => *seconds,
+        }
+    }
+
+    /// Current time, in the interval's unit
+    pub fn now(&self, clock: &Clock) -> u64 {
+        match self {
+            Interval::Slots(_) => clock.slot,
+            Interval::Seconds(_) => clock.unix_timestamp.max(0) as u64,
+        }
+    }
+}
+
+/// A split paid out every interval from lamports held in the account itself.
+/// Payments are made in arrears, the first one interval after creation.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct Subscription {
+    /// Always `AccountKind::Subscription`
+    pub kind: AccountKind,
+    /// Key allowed to cancel the subscription
+    pub authority: Pubkey,
+    /// Identifier distinguishing the authority's subscriptions, part of the address seeds
+    pub id: u64,
+    /// Bump seed of the subscription's program address
+    pub bump: u8,
+    /// Lamports split every interval
+    pub amount: u64,
+    /// Time between payments
+    pub interval: Interval,
+    /// When the next payment is due, in the interval's unit
+    pub next_due: u64,
+    /// Where lamports left over after rounding go
+    pub remainder: RemainderPolicy,
+    /// Recipient addresses, in payment order
+    pub recipients: Vec<Pubkey>,
+    /// One weight per recipient
+    pub weights: Weights,
+}
+
+impl Subscription {
+    /// Finds the program address of `authority`'s subscription `id`
+    pub fn find_address(program_id: &Pubkey, authority: &Pubkey, id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[SUBSCRIPTION_SEED, authority.as_ref(), &id.to_le_bytes()],
+            program_id,
+        )
+    }
+
+    /// Lamports owed for the part of the current period that has elapsed
+    /// at `now`, which must be before the next payment is due
+    pub fn prorated_amount(&self, now: u64) -> u64 {
+        let length = self.interval.length();
+        let elapsed = length.saturating_sub(self.next_due.saturating_sub(now));
+        (u128::from(self.amount) * u128::from(elapsed) / u128::from(length)) as u64
+    }
+}
+
+impl ProgramAccount for Subscription {
+    const KIND: AccountKind = AccountKind::Subscription;
+
+    // kind + authority + id + bump + amount + interval + next_due + remainder + recipients
+    // + weights as shares
+    const LEN: usize =
+        1 + 32 + 8 + 1 + 8 + (1 + 8) + 8 + 1 + (4 + 32 * MAX_PAYEES) + (1 + 4 + 8 * MAX_PAYEES);
+
+    fn kind(&self) -> AccountKind {
+        self.kind
+    }
+}
+
+/// Lamports locked for one recipient of a vesting schedule
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct Grant {
+    /// Recipient allowed to claim
+    pub recipient: Pubkey,
+    /// Lamports granted, reduced to the vested amount if the schedule is revoked
+    pub amount: u64,
+    /// Lamports the recipient has claimed so far
+    pub claimed: u64,
+}
+
+/// Lamports held in the account and unlocked linearly between `start` and
+/// `start + duration`, with nothing claimable before the cliff
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct Vesting {
+    /// Always `AccountKind::Vesting`
+    pub kind: AccountKind,
+    /// Key allowed to revoke and close the schedule
+    pub authority: Pubkey,
+    /// Identifier distinguishing the authority's schedules, part of the address seeds
+    pub id: u64,
+    /// Bump seed of the schedule's program address
+    pub bump: u8,
+    /// Unix time vesting starts
+    pub start: i64,
+    /// Seconds after `start` before anything can be claimed
+    pub cliff: i64,
+    /// Seconds after `start` until everything has vested
+    pub duration: i64,
+    /// Whether the authority revoked the unvested lamports
+    pub revoked: bool,
+    /// One grant per recipient
+    pub grants: Vec<Grant>,
+}
+
+impl Vesting {
+    /// Finds the program address of `authority`'s schedule `id`
+    pub fn find_address(program_id: &Pubkey, authority: &Pubkey, id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[VESTING_SEED, authority.as_ref(), &id.to_le_bytes()],
+            program_id,
+        )
+    }
+
+    /// Checks the cliff falls within a non-empty vesting period
+    pub fn validate_schedule(cliff: i64, duration: i64) -> Result<(), ProgramError> {
+        if duration <= 0 || cliff < 0 || cliff > duration {
+            msg!("Cliff of {}s doesn't fit a {}s schedule", cliff, duration);
+            return Err(SplitError::InvalidSchedule.into());
+        }
+        Ok(())
+    }
+
+    /// Lamports of `grant` vested at unix time `now`
+    pub fn vested(&self, grant: &Grant, now: i64) -> u64 {
+        if self.revoked {
+            return grant.amount;
+        }
+        let elapsed = now.saturating_sub(self.start);
+        if elapsed < self.cliff {
+            0
+        } else if elapsed >= self.duration {
+            grant.amount
+        } else {
+            (u128::from(grant.amount) * elapsed as u128 / self.duration as u128) as u64
+        }
+    }
+
+    /// Lamports granted that haven't been claimed yet
+    pub fn outstanding(&self) -> u64 {
+        self.grants
+            .iter()
+            .map(|grant| grant.amount - grant.claimed)
+            .sum()
+    }
+}
+
+impl ProgramAccount for Vesting {
+    const KIND: AccountKind = AccountKind::Vesting;
+
+    // kind + authority + id + bump + start + cliff + duration + revoked + grants
+    const LEN: usize = 1 + 32 + 8 + 1 + 8 + 8 + 8 + 1 + (4 + (32 + 8 + 8) * MAX_PAYEES);
+
+    fn kind(&self) -> AccountKind {
+        self.kind
+    }
+}
+
+/// A vault whose lamports are split on the authorization of its signers.
+/// One signer alone can split up to `limit` lamports each `period`, in one
+/// split or several. Splits past that need `threshold` signers. Every signer
+/// is trusted with `limit` lamports a period without the others.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct Multisig {
+    /// Always `AccountKind::Multisig`
+    pub kind: AccountKind,
+    /// Key that created the vault, part of the address seeds
+    pub creator: Pubkey,
+    /// Identifier distinguishing the creator's vaults, part of the address seeds
+    pub id: u64,
+    /// Bump seed of the vault's program address
+    pub bump: u8,
+    /// Keys allowed to authorize splits
+    pub signers: Vec<Pubkey>,
+    /// Signers needed to authorize a split past `limit`
+    pub threshold: u8,
+    /// Lamports that can be split on fewer than `threshold` signers each
+    /// period
+    pub limit: u64,
+    /// Length of the period `limit` applies to
+    pub period: Interval,
+    /// Start of the current period, in the unit of `period`
+    pub period_start: u64,
+    /// Lamports split on fewer than `threshold` signers this period
+    pub spent: u64,
+    /// Number of proposals created, the index of the next one
+    pub proposal_count: u64,
+}
+
+impl Multisig {
+    /// Finds the program address of `creator`'s vault `id`
+    pub fn find_address(program_id: &Pubkey, creator: &Pubkey, id: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[MULTISIG_SEED, creator.as_ref(), &id.to_le_bytes()],
+            program_id,
+        )
+    }
+
+    /// Checks the signers are distinct, the threshold is reachable and the
+    /// period has a length
+    pub fn validate(
+        signers: &[Pubkey],
+        threshold: u8,
+        period: &Interval,
+    ) -> Result<(), ProgramError> {
+        if period.length() == 0 {
+            return Err(SplitError::InvalidInterval.into());
+        }
+        if signers.is_empty() || signers.len() > MAX_MULTISIG_SIGNERS {
+            msg!(
+                "Multisig has {} signers, max is {}",
+                signers.len(),
+                MAX_MULTISIG_SIGNERS
+            );
+            return Err(SplitError::InvalidThreshold.into());
+        }
+        if threshold == 0 || threshold as usize > signers.len() {
+            msg!("Threshold {} of {} signers", threshold, signers.len());
+            return Err(SplitError::InvalidThreshold.into());
+        }
+        for (index, signer) in signers.iter().enumerate() {
+            if signers[..index].contains(signer) {
+                msg!("Signer {} is listed more than once", signer);
+                return Err(SplitError::InvalidThreshold.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Position of `key` among the signers
+    pub fn signer_index(&self, key: &Pubkey) -> Option<usize> {
+        self.signers.iter().position(|signer| signer == key)
+    }
+
+    /// Starts a new period if the current one ended before `now`, in the
+    /// unit of `period`
+    pub fn roll_period(&mut self, now: u64) {
+        let length = self.period.length();
+        if now.saturating_sub(self.period_start) >= length {
+            self.period_start = now - (now - self.period_start) % length;
+            self.spent = 0;
+        }
+    }
+
+    /// Signers needed to authorize a split of `amount` lamports, counting
+    /// what was already split on fewer than `threshold` signers this period
+    pub fn required_approvals(&self, amount: u64) -> usize {
+        if self.spent.saturating_add(amount) > self.limit {
+            self.threshold as usize
+        } else {
+            1
+        }
+    }
+
+    /// Records a split of `amount` lamports authorized by `approvals`
+    /// signers, counting it against `limit` unless the threshold approved it
+    pub fn record_split(&mut self, amount: u64, approvals: usize) {
+        if approvals < self.threshold as usize {
+            self.spent = self.spent.saturating_add(amount);
+        }
+    }
+}
+
+impl ProgramAccount for Multisig {
+    const KIND: AccountKind = AccountKind::Multisig;
+
+    // kind + creator + id + bump + signers + threshold + limit + period
+    // + period_start + spent + proposal_count
+    const LEN: usize =
+        1 + 32 + 8 + 1 + (4 + 32 * MAX_MULTISIG_SIGNERS) + 1 + 8 + (1 + 8) + 8 + 8 + 8;
+
+    fn kind(&self) -> AccountKind {
+        self.kind
+    }
+}
+
+/// A split out of a multisig vault that signers approve one at a time. The
+/// approval that reaches the required count executes it.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct Proposal {
+    /// Always `AccountKind::Proposal`
+    pub kind: AccountKind,
+    /// Vault the split is paid from
+    pub multisig: Pubkey,
+    /// Signer that proposed the split and funded the account
+    pub proposer: Pubkey,
+    /// Position among the vault's proposals, part of the address seeds
+    pub index: u64,
+    /// Lamports to split
+    pub amount: u64,
+    /// Where lamports left over after rounding go
+    pub remainder: RemainderPolicy,
+    /// Recipient addresses, in payment order
+    pub recipients: Vec<Pubkey>,
+    /// One weight per recipient
+    pub weights: Weights,
+    /// Whether each of the vault's signers has approved, in signer order
+    pub approvals: Vec<bool>,
+    /// Whether the split has been paid
+    pub executed: bool,
+}
+
+impl Proposal {
+    /// Finds the program address of `multisig`'s proposal `index`
+    pub fn find_address(program_id: &Pubkey, multisig: &Pubkey, index: u64) -> (Pubkey, u8) {
+        Pubkey::find_program_address(
+            &[PROPOSAL_SEED, multisig.as_ref(), &index.to_le_bytes()],
+            program_id,
+        )
+    }
+
+    /// Number of signers that have approved
+    pub fn approval_count(&self) -> usize {
+        self.approvals.iter().filter(|approved| **approved).count()
+    }
+}
+
+impl ProgramAccount for Proposal {
+    const KIND: AccountKind = AccountKind::Proposal;
+
+    // kind + multisig + proposer + index + amount + remainder + recipients
+    // + weights as shares + approvals + executed
+    const LEN: usize = 1
+        + 32
+        + 32
+        + 8
+        + 8
+        + 1
+        + (4 + 32 * MAX_PAYEES)
+        + (1 + 4 + 8 * MAX_PAYEES)
+        + (4 + MAX_MULTISIG_SIGNERS)
+        + 1;
+
+    fn kind(&self) -> AccountKind {
+        self.kind
+    }
+}
+
+/// Record of one completed split, chained to the receipt before it
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct Receipt {
+    /// Slot the split was paid in
+    pub slot: u64,
+    /// Lamports paid in total
+    pub amount: u64,
+    /// Accounts credited, in payee order
+    pub payees: Vec<Pubkey>,
+    /// Lamports credited to each payee
+    pub shares: Vec<u64>,
+    /// Hash of the receipt before this one, default for the first receipt
+    pub previous_hash: Hash,
+}
+
+impl Receipt {
+    /// Hash committing to every field, including the previous hash
+    pub fn hash(&self) -> Hash {
+        let mut data = vec![
+            self.previous_hash.as_ref().to_vec(),
+            self.slot.to_le_bytes().to_vec(),
+            self.amount.to_le_bytes().to_vec(),
+        ];
+        data.extend(self.payees.iter().map(|payee| payee.to_bytes().to_vec()));
+        data.extend(self.shares.iter().map(|share| share.to_le_bytes().to_vec()));
+        let slices: Vec<&[u8]> = data.iter().map(|bytes| bytes.as_slice()).collect();
+        hashv(&slices)
+    }
+}
+
+/// Ring buffer of a payer's most recent receipts. Each receipt holds the
+/// hash of the one before it, so rewriting any receipt breaks the chain.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct ReceiptLog {
+    /// Always `AccountKind::ReceiptLog`
+    pub kind: AccountKind,
+    /// Account whose splits are recorded, part of the address seeds
+    pub payer: Pubkey,
+    /// Bump seed of the log's program address
+    pub bump: u8,
+    /// Receipts held before the oldest is overwritten
+    pub capacity: u32,
+    /// Receipts appended since the log was created
+    pub count: u64,
+    /// Hash of the newest receipt, default while the log is empty
+    pub last_hash: Hash,
+    /// Receipt `n` is stored at position `n % capacity`
+    pub receipts: Vec<Receipt>,
+}
+
+impl ReceiptLog {
+    // kind + payer + bump + capacity + count + last hash + vec length
+    const HEADER_LEN: usize = 1 + 32 + 1 + 4 + 8 + 32 + 4;
+
+    // slot + amount + payees + shares + previous hash
+    const RECEIPT_LEN: usize = 8 + 8 + (4 + 32 * MAX_PAYEES) + (4 + 8 * MAX_PAYEES) + 32;
+
+    /// Size of a receipt log account that holds up to `capacity` receipts
+    pub fn space(capacity: usize) -> usize {
+        Self::HEADER_LEN + Self::RECEIPT_LEN * capacity
+    }
+
+    /// Finds the program address of `payer`'s receipt log
+    pub fn find_address(program_id: &Pubkey, payer: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[RECEIPT_LOG_SEED, payer.as_ref()], program_id)
+    }
+
+    /// Appends a receipt chained to the newest one, overwriting the oldest
+    /// once the log is full
+    pub fn append(&mut self, slot: u64, payees: Vec<Pubkey>, shares: Vec<u64>) {
+        let receipt = Receipt {
+            slot,
+            amount: shares.iter().sum(),
+            payees,
+            shares,
+            previous_hash: self.last_hash,
+        };
+        self.last_hash = receipt.hash();
+        let position = (self.count % u64::from(self.capacity)) as usize;
+        if position < self.receipts.len() {
+            self.receipts[position] = receipt;
+        } else {
+            self.receipts.push(receipt);
+        }
+        self.count += 1;
+    }
+
+    /// Receipts still held, oldest first
+    pub fn history(&self) -> Vec<&Receipt> {
+        let oldest = (self.count % u64::from(self.capacity.max(1))) as usize;
+        let (newer, older) = self.receipts.split_at(oldest.min(self.receipts.len()));
+        older.iter().chain(newer).collect()
+    }
+
+    /// Checks that each held receipt hashes to the next one's previous hash
+    /// and the newest to `last_hash`. A log that has never wrapped must start
+    /// from the default hash. Meant for clients auditing a fetched log.
+    pub fn verify(&self) -> Result<(), SplitError> {
+        let history = self.history();
+        if history.len() as u64 != self.count.min(u64::from(self.capacity)) {
+            return Err(SplitError::BrokenReceiptChain);
+        }
+        let mut expected = match history.first() {
+            Some(oldest) if self.count <= u64::from(self.capacity) => {
+                if oldest.previous_hash != Hash::default() {
+                    return Err(SplitError::BrokenReceiptChain);
+                }
+                Hash::default()
+            }
+            Some(oldest) => oldest.previous_hash,
+            None => Hash::default(),
+        };
+        for receipt in history {
+            if receipt.previous_hash != expected || receipt.shares.len() != receipt.payees.len() {
+                return Err(SplitError::BrokenReceiptChain);
+            }
+            expected = receipt.hash();
+        }
+        if expected != self.last_hash {
+            return Err(SplitError::BrokenReceiptChain);
+        }
+        Ok(())
+    }
+}
+
+impl ProgramAccount for ReceiptLog {
+    const KIND: AccountKind = AccountKind::ReceiptLog;
+
+    const LEN: usize = Self::HEADER_LEN + Self::RECEIPT_LEN * MAX_RECEIPTS;
+
+    fn kind(&self) -> AccountKind {
+        self.kind
+    }
+}
+
+/// Running total of what one payee has received from a split group
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct PayeeLedger {
+    /// Always `AccountKind::PayeeLedger`
+    pub kind: AccountKind,
+    /// Group the payee is paid through, part of the address seeds
+    pub group: Pubkey,
+    /// Payee whose earnings are recorded, part of the address seeds
+    pub payee: Pubkey,
+    /// Bump seed of the ledger's program address
+    pub bump: u8,
+    /// Lamports received in splits the ledger was passed to
+    pub total_received: u64,
+    /// Number of splits that credited the ledger
+    pub split_count: u64,
+    /// Slot of the most recent credit, zero if none
+    pub last_slot: u64,
+}
+
+impl PayeeLedger {
+    /// Finds the program address of `payee`'s ledger for `group`
+    pub fn find_address(program_id: &Pubkey, group: &Pubkey, payee: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[LEDGER_SEED, group.as_ref(), payee.as_ref()], program_id)
+    }
+
+    /// Adds a split's payment to the running total
+    pub fn credit(&mut self, amount: u64, slot: u64) -> Result<(), ProgramError> {
+        self.total_received = self
+            .total_received
+            .checked_add(amount)
+            .ok_or(SplitError::Overflow)?;
+        self.split_count += 1;
+        self.last_slot = slot;
+        Ok(())
+    }
+}
+
+impl ProgramAccount for PayeeLedger {
+    const KIND: AccountKind = AccountKind::PayeeLedger;
+
+    // kind + group + payee + bump + total received + split count + last slot
+    const LEN: usize = 1 + 32 + 32 + 1 + 8 + 8 + 8;
+
+    fn kind(&self) -> AccountKind {
+        self.kind
+    }
+}
+
+/// Marks a split id as used by its payer, so a retried split isn't paid
+/// twice. Records are never closed, since a closed record would let the id
+/// be paid again.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct SplitRecord {
+    /// Always `AccountKind::SplitRecord`
+    pub kind: AccountKind,
+    /// Account the split was paid from, part of the address seeds
+    pub payer: Pubkey,
+    /// Id chosen by the caller, part of the address seeds
+    pub split_id: [u8; 32],
+    /// Slot the split was paid in
+    pub slot: u64,
+}
+
+impl SplitRecord {
+    /// Finds the program address recording `payer`'s split `split_id`
+    pub fn find_address(program_id: &Pubkey, payer: &Pubkey, split_id: &[u8; 32]) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[SPLIT_RECORD_SEED, payer.as_ref(), split_id], program_id)
+    }
+}
+
+impl ProgramAccount for SplitRecord {
+    const KIND: AccountKind = AccountKind::SplitRecord;
+
+    // kind + payer + split id + slot
+    const LEN: usize = 1 + 32 + 32 + 8;
+
+    fn kind(&self) -> AccountKind {
+        self.kind
+    }
+}
@@ -0,0 +1,111 @@
+use helloworld::{
+    chunk::{chunk_split, chunk_split_id, transaction_size, MAX_TRANSACTION_SIZE},
+    error::SplitError,
+    process_instruction,
+    split::{DuplicatePolicy, DustPolicy, RemainderPolicy, Weights, MAX_PAYEES},
+};
+use solana_program_test::*;
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::Signer,
+    transaction::{Transaction, TransactionError},
+};
+
+#[test]
+fn test_chunk_split() {
+    let program_id = Pubkey::new_unique();
+    let payer = Pubkey::new_unique();
+    let payees: Vec<Pubkey> = (0..25).map(|_| Pubkey::new_unique()).collect();
+
+    let chunked = chunk_split(
+        &program_id,
+        &payer,
+        &payees,
+        1_000_003,
+        &Weights::Shares((1..=25).collect()),
+        RemainderPolicy::LargestRemainder,
+        DuplicatePolicy::Reject,
+        &[0; 25],
+        DustPolicy::Allow,
+        Some([1; 32]),
+        None,
+    )
+    .unwrap();
+
+    // The remainder is handed out once across every chunk
+    assert_eq!(chunked.total(), 1_000_003);
+    assert_eq!(
+        chunked
+            .chunks
+            .iter()
+            .map(|chunk| chunk.total())
+            .sum::<u64>(),
+        1_000_003
+    );
+    let mut split_ids = vec![];
+    for chunk in chunked.chunks.iter() {
+        assert!(transaction_size(&payer, &chunk.instructions) <= MAX_TRANSACTION_SIZE);
+        for instruction in chunk.instructions.iter() {
+            assert!(instruction.accounts.len() <= MAX_PAYEES + 3);
+        }
+        split_ids.extend_from_slice(&chunk.split_ids);
+    }
+    assert_eq!(split_ids[0], chunk_split_id(&[1; 32], 0));
+    split_ids.dedup();
+    assert_eq!(split_ids.len(), 3);
+}
+
+#[tokio::test]
+async fn test_send_chunks() {
+    let program_id = Pubkey::new_unique();
+    let payees: Vec<Pubkey> = (0..25).map(|_| Pubkey::new_unique()).collect();
+
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let chunked = chunk_split(
+        &program_id,
+        &payer.pubkey(),
+        &payees,
+        250_000_001,
+        &Weights::Shares(vec![1; 25]),
+        RemainderPolicy::FirstPayee,
+        DuplicatePolicy::Reject,
+        &[0; 25],
+        DustPolicy::Allow,
+        Some([1; 32]),
+        Some("batch 1"),
+    )
+    .unwrap();
+    for chunk in chunked.chunks.iter() {
+        let mut transaction =
+            Transaction::new_with_payer(&chunk.instructions, Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    assert_eq!(
+        banks_client.get_balance(payees[0]).await.unwrap(),
+        10_000_001
+    );
+    for payee in payees[1..].iter() {
+        assert_eq!(banks_client.get_balance(*payee).await.unwrap(), 10_000_000);
+    }
+
+    // Resending an instruction that was paid fails rather than paying twice
+    let mut transaction =
+        Transaction::new_with_payer(&chunked.chunks[0].instructions[..1], Some(&payer.pubkey()));
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SplitError::SplitIdUsed as u32)
+        )
+    );
+}
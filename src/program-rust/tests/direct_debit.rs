@@ -0,0 +1,149 @@
+// Kept in its own test binary: the logger that captures split events must be
+// installed before `ProgramTest` sets up its own
+use helloworld::{
+    event::SplitEvent,
+    instruction::{create_multisig, multisig_split, weighted_split},
+    process_instruction,
+    split::{DuplicatePolicy, DustPolicy, MemoPolicy, RemainderPolicy, Weights},
+    state::{Interval, Multisig},
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+use std::sync::{Arc, Mutex};
+
+/// Collects the split events the runtime writes to the debug log
+struct EventLogger(Arc<Mutex<Vec<SplitEvent>>>);
+
+impl log::Log for EventLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        if let Some(Ok(event)) = SplitEvent::from_log(&record.args().to_string()) {
+            self.0.lock().unwrap().push(event);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[tokio::test]
+async fn test_direct_debit_matches_system_transfer() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    log::set_boxed_logger(Box::new(EventLogger(events.clone()))).unwrap();
+    log::set_max_level(log::LevelFilter::Debug);
+
+    let program_id = Pubkey::new_unique();
+    let signer = Keypair::new();
+    // A system account pays through the system program, the multisig is
+    // owned by this program and debited directly
+    let funder = Keypair::new();
+    let system_payees = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let multisig_payees = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        funder.pubkey(),
+        Account {
+            lamports: 100_000_000,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let (multisig, _) = Multisig::find_address(&program_id, &payer.pubkey(), 1);
+
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            create_multisig(
+                &program_id,
+                &payer.pubkey(),
+                1,
+                vec![signer.pubkey()],
+                1,
+                100_000_000,
+                Interval::Slots(100),
+            ),
+            system_instruction::transfer(&payer.pubkey(), &multisig, 100_000_000),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    let multisig_balance = banks_client.get_balance(multisig).await.unwrap();
+
+    // The same uneven split from each, leaving a remainder with the payer
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            weighted_split(
+                &program_id,
+                &funder.pubkey(),
+                &system_payees,
+                20_000_001,
+                Weights::Shares(vec![3, 1]),
+                RemainderPolicy::Payer,
+                DuplicatePolicy::Reject,
+                DustPolicy::Allow,
+                None,
+                MemoPolicy::None,
+            ),
+            multisig_split(
+                &program_id,
+                &multisig,
+                &multisig_payees,
+                &[signer.pubkey()],
+                20_000_001,
+                Weights::Shares(vec![3, 1]),
+                RemainderPolicy::Payer,
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &funder, &signer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    for (system_payee, multisig_payee) in system_payees.iter().zip(&multisig_payees) {
+        assert_eq!(
+            banks_client.get_balance(*system_payee).await.unwrap(),
+            banks_client.get_balance(*multisig_payee).await.unwrap()
+        );
+    }
+    assert_eq!(
+        banks_client.get_balance(system_payees[0]).await.unwrap(),
+        15_000_000
+    );
+    assert_eq!(
+        banks_client.get_balance(funder.pubkey()).await.unwrap(),
+        80_000_000
+    );
+    assert_eq!(
+        banks_client.get_balance(multisig).await.unwrap(),
+        multisig_balance - 20_000_000
+    );
+
+    let events = events.lock().unwrap();
+    let event = |payer: &Pubkey| {
+        events
+            .iter()
+            .find(|event| event.payer == *payer)
+            .cloned()
+            .unwrap()
+    };
+    let (system_event, multisig_event) = (event(&funder.pubkey()), event(&multisig));
+    let amounts = |event: &SplitEvent| -> Vec<u64> {
+        event.payees.iter().map(|payee| payee.amount).collect()
+    };
+    assert_eq!(amounts(&system_event), vec![15_000_000, 5_000_000]);
+    assert_eq!(amounts(&system_event), amounts(&multisig_event));
+    assert_eq!(system_event.remainder, multisig_event.remainder);
+    assert_eq!(system_event.policy, multisig_event.policy);
+    assert_eq!(system_event.memo, multisig_event.memo);
+    assert_eq!(system_event.mint, multisig_event.mint);
+}
@@ -0,0 +1,472 @@
+use helloworld::{
+    error::SplitError,
+    instruction::{
+        add_distribution_recipients, cancel_distribution, close_distribution,
+        continue_distribution, create_distribution, start_distribution,
+    },
+    process_instruction,
+    split::RemainderPolicy,
+    state::PagedDistribution,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    hash::Hash,
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::{Transaction, TransactionError},
+};
+
+/// Processes `instruction`, signed by the fee payer and `signers`
+async fn process(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    signers: &[&Keypair],
+    recent_blockhash: Hash,
+    instruction: Instruction,
+) -> Result<(), TransactionError> {
+    let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+    let mut signers = signers.to_vec();
+    signers.push(payer);
+    transaction.sign(&signers, recent_blockhash);
+    banks_client
+        .process_transaction(transaction)
+        .await
+        .map_err(|err| err.unwrap())
+}
+
+fn custom_error(error: SplitError) -> TransactionError {
+    TransactionError::InstructionError(0, InstructionError::Custom(error as u32))
+}
+
+#[tokio::test]
+async fn test_paged_distribution() {
+    let program_id = Pubkey::new_unique();
+    let recipients: Vec<Pubkey> = (0..25).map(|_| Pubkey::new_unique()).collect();
+    // Anyone can pay the next page, so pages are cranked by a separate fee payer
+    let cranker = Keypair::new();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        cranker.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let (distribution, _) = PagedDistribution::find_address(&program_id, &payer.pubkey(), 1);
+
+    // Create the distribution, add recipients in batches, then fund it
+    let mut instructions = vec![create_distribution(
+        &program_id,
+        &payer.pubkey(),
+        1,
+        1_003,
+        recipients.len() as u32,
+        RemainderPolicy::LargestRemainder,
+    )];
+    for batch in recipients.chunks(10) {
+        instructions.push(add_distribution_recipients(
+            &program_id,
+            &payer.pubkey(),
+            &distribution,
+            batch.to_vec(),
+            vec![1; batch.len()],
+        ));
+    }
+    instructions.push(start_distribution(
+        &program_id,
+        &payer.pubkey(),
+        &distribution,
+    ));
+    for instruction in instructions {
+        let mut transaction = Transaction::new_with_payer(&[instruction], Some(&payer.pubkey()));
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+    }
+
+    // Pay a page at a time
+    for (page, batch) in recipients.chunks(10).enumerate() {
+        let mut transaction = Transaction::new_with_payer(
+            &[continue_distribution(
+                &program_id,
+                &distribution,
+                (page * 10) as u32,
+                batch,
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer], recent_blockhash);
+        banks_client.process_transaction(transaction).await.unwrap();
+
+        // Retrying the same page pays nobody twice
+        let mut transaction = Transaction::new_with_payer(
+            &[continue_distribution(
+                &program_id,
+                &distribution,
+                (page * 10) as u32,
+                batch,
+            )],
+            Some(&cranker.pubkey()),
+        );
+        transaction.sign(&[&cranker], recent_blockhash);
+        assert_eq!(
+            banks_client
+                .process_transaction(transaction)
+                .await
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(SplitError::StaleCursor as u32)
+            )
+        );
+    }
+
+    let mut total = 0;
+    for recipient in recipients.iter() {
+        let balance = banks_client.get_balance(*recipient).await.unwrap();
+        assert!(balance == 40 || balance == 41);
+        total += balance;
+    }
+    assert_eq!(total, 1_003);
+
+    // The finished distribution can be closed
+    let mut transaction = Transaction::new_with_payer(
+        &[close_distribution(
+            &program_id,
+            &payer.pubkey(),
+            &distribution,
+            &payer.pubkey(),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    assert!(banks_client
+        .get_account(distribution)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_distribution_remainder_stays_with_authority() {
+    let program_id = Pubkey::new_unique();
+    let recipients: Vec<Pubkey> = (0..25).map(|_| Pubkey::new_unique()).collect();
+    // Fees are paid separately so the authority's balance only moves by
+    // what it funds
+    let authority = Keypair::new();
+    let destination = Pubkey::new_unique();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        authority.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let (distribution, _) = PagedDistribution::find_address(&program_id, &authority.pubkey(), 1);
+
+    // 25 recipients of 40 lamports leave a remainder of 3 with the authority
+    process(
+        &mut banks_client,
+        &payer,
+        &[&authority],
+        recent_blockhash,
+        create_distribution(
+            &program_id,
+            &authority.pubkey(),
+            1,
+            1_003,
+            recipients.len() as u32,
+            RemainderPolicy::Payer,
+        ),
+    )
+    .await
+    .unwrap();
+    for batch in recipients.chunks(10) {
+        process(
+            &mut banks_client,
+            &payer,
+            &[&authority],
+            recent_blockhash,
+            add_distribution_recipients(
+                &program_id,
+                &authority.pubkey(),
+                &distribution,
+                batch.to_vec(),
+                vec![1; batch.len()],
+            ),
+        )
+        .await
+        .unwrap();
+    }
+    let rent = banks_client.get_balance(distribution).await.unwrap();
+    let authority_balance = banks_client.get_balance(authority.pubkey()).await.unwrap();
+
+    process(
+        &mut banks_client,
+        &payer,
+        &[&authority],
+        recent_blockhash,
+        start_distribution(&program_id, &authority.pubkey(), &distribution),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        banks_client.get_balance(authority.pubkey()).await.unwrap(),
+        authority_balance - 1_000
+    );
+    assert_eq!(
+        banks_client.get_balance(distribution).await.unwrap(),
+        rent + 1_000
+    );
+
+    for (page, batch) in recipients.chunks(10).enumerate() {
+        process(
+            &mut banks_client,
+            &payer,
+            &[],
+            recent_blockhash,
+            continue_distribution(&program_id, &distribution, (page * 10) as u32, batch),
+        )
+        .await
+        .unwrap();
+    }
+    for recipient in recipients.iter() {
+        assert_eq!(banks_client.get_balance(*recipient).await.unwrap(), 40);
+    }
+    assert_eq!(banks_client.get_balance(distribution).await.unwrap(), rent);
+
+    // Closing returns only the rent, nothing was left over
+    process(
+        &mut banks_client,
+        &payer,
+        &[&authority],
+        recent_blockhash,
+        close_distribution(
+            &program_id,
+            &authority.pubkey(),
+            &distribution,
+            &destination,
+        ),
+    )
+    .await
+    .unwrap();
+    assert_eq!(banks_client.get_balance(destination).await.unwrap(), rent);
+    assert!(banks_client
+        .get_account(distribution)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_invalid_distribution_recipients() {
+    let program_id = Pubkey::new_unique();
+    let recipients: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+    let authority = Keypair::new();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        authority.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let (distribution, _) = PagedDistribution::find_address(&program_id, &authority.pubkey(), 1);
+
+    process(
+        &mut banks_client,
+        &payer,
+        &[&authority],
+        recent_blockhash,
+        create_distribution(
+            &program_id,
+            &authority.pubkey(),
+            1,
+            1_000,
+            10,
+            RemainderPolicy::Payer,
+        ),
+    )
+    .await
+    .unwrap();
+    process(
+        &mut banks_client,
+        &payer,
+        &[&authority],
+        recent_blockhash,
+        add_distribution_recipients(
+            &program_id,
+            &authority.pubkey(),
+            &distribution,
+            recipients[..2].to_vec(),
+            vec![1; 2],
+        ),
+    )
+    .await
+    .unwrap();
+
+    // A duplicate within the batch, a recipient added by an earlier batch,
+    // the system program and the distribution itself are all rejected
+    for (batch, error) in [
+        (
+            vec![recipients[2], recipients[2]],
+            SplitError::DuplicatePayee,
+        ),
+        (vec![recipients[0]], SplitError::DuplicatePayee),
+        (vec![system_program::id()], SplitError::SystemProgramIsPayee),
+        (vec![distribution], SplitError::PayerIsPayee),
+    ]
+    .iter()
+    {
+        assert_eq!(
+            process(
+                &mut banks_client,
+                &payer,
+                &[&authority],
+                recent_blockhash,
+                add_distribution_recipients(
+                    &program_id,
+                    &authority.pubkey(),
+                    &distribution,
+                    batch.clone(),
+                    vec![1; batch.len()],
+                ),
+            )
+            .await
+            .unwrap_err(),
+            custom_error(error.clone())
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_cancel_distribution() {
+    let program_id = Pubkey::new_unique();
+    let recipients: Vec<Pubkey> = (0..25).map(|_| Pubkey::new_unique()).collect();
+    let authority = Keypair::new();
+    let destination = Pubkey::new_unique();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        authority.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let (distribution, _) = PagedDistribution::find_address(&program_id, &authority.pubkey(), 1);
+
+    process(
+        &mut banks_client,
+        &payer,
+        &[&authority],
+        recent_blockhash,
+        create_distribution(
+            &program_id,
+            &authority.pubkey(),
+            1,
+            1_000,
+            recipients.len() as u32,
+            RemainderPolicy::Payer,
+        ),
+    )
+    .await
+    .unwrap();
+    process(
+        &mut banks_client,
+        &payer,
+        &[&authority],
+        recent_blockhash,
+        add_distribution_recipients(
+            &program_id,
+            &authority.pubkey(),
+            &distribution,
+            recipients.clone(),
+            vec![1; recipients.len()],
+        ),
+    )
+    .await
+    .unwrap();
+    let rent = banks_client.get_balance(distribution).await.unwrap();
+
+    // Only a started distribution can be cancelled, an open one is closed
+    assert_eq!(
+        process(
+            &mut banks_client,
+            &payer,
+            &[&authority],
+            recent_blockhash,
+            cancel_distribution(
+                &program_id,
+                &authority.pubkey(),
+                &distribution,
+                &authority.pubkey(),
+            ),
+        )
+        .await
+        .unwrap_err(),
+        custom_error(SplitError::InvalidDistributionStatus)
+    );
+
+    process(
+        &mut banks_client,
+        &payer,
+        &[&authority],
+        recent_blockhash,
+        start_distribution(&program_id, &authority.pubkey(), &distribution),
+    )
+    .await
+    .unwrap();
+    process(
+        &mut banks_client,
+        &payer,
+        &[],
+        recent_blockhash,
+        continue_distribution(&program_id, &distribution, 0, &recipients[..10]),
+    )
+    .await
+    .unwrap();
+
+    // The 15 unpaid recipients' 600 lamports come back with the rent
+    process(
+        &mut banks_client,
+        &payer,
+        &[&authority],
+        recent_blockhash,
+        cancel_distribution(
+            &program_id,
+            &authority.pubkey(),
+            &distribution,
+            &destination,
+        ),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        banks_client.get_balance(destination).await.unwrap(),
+        rent + 600
+    );
+    assert_eq!(banks_client.get_balance(recipients[9]).await.unwrap(), 40);
+    assert_eq!(banks_client.get_balance(recipients[10]).await.unwrap(), 0);
+    assert!(banks_client
+        .get_account(distribution)
+        .await
+        .unwrap()
+        .is_none());
+}
@@ -0,0 +1,100 @@
+use helloworld::{
+    error::SplitError,
+    instruction::{close_escrow, create_escrow, deposit_to_escrow, withdraw},
+    process_instruction,
+    split::{RemainderPolicy, Weights},
+    state::Escrow,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+#[tokio::test]
+async fn test_escrow_withdrawals() {
+    let program_id = Pubkey::new_unique();
+    let payees = [Keypair::new(), Keypair::new()];
+    let payee_keys: Vec<Pubkey> = payees.iter().map(|payee| payee.pubkey()).collect();
+
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let (escrow, _) = Escrow::find_address(&program_id, &payer.pubkey(), 1);
+
+    // Deposit 60/40 without passing any payee accounts
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            create_escrow(&program_id, &payer.pubkey(), 1, 10),
+            deposit_to_escrow(
+                &program_id,
+                &payer.pubkey(),
+                &escrow,
+                1_000,
+                payee_keys.clone(),
+                Weights::BasisPoints(vec![6_000, 4_000]),
+                RemainderPolicy::RejectUneven,
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    let rent = banks_client
+        .get_rent()
+        .await
+        .unwrap()
+        .minimum_balance(Escrow::space(10));
+    assert_eq!(
+        banks_client.get_balance(escrow).await.unwrap(),
+        rent + 1_000
+    );
+
+    // The first payee claims their share
+    let mut transaction = Transaction::new_with_payer(
+        &[withdraw(&program_id, &payees[0].pubkey(), &escrow)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &payees[0]], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(
+        banks_client.get_balance(payees[0].pubkey()).await.unwrap(),
+        600
+    );
+
+    // The escrow can't close while the second payee is still owed
+    let mut transaction = Transaction::new_with_payer(
+        &[close_escrow(
+            &program_id,
+            &payer.pubkey(),
+            &escrow,
+            &payer.pubkey(),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SplitError::EntitlementsOutstanding as u32)
+        )
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        &[withdraw(&program_id, &payees[1].pubkey(), &escrow)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &payees[1]], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(
+        banks_client.get_balance(payees[1].pubkey()).await.unwrap(),
+        400
+    );
+    assert_eq!(banks_client.get_balance(escrow).await.unwrap(), rent);
+}
@@ -0,0 +1,94 @@
+use helloworld::{
+    error::SplitError,
+    event::{parse_logs, SplitEvent, EVENT_LOG_PREFIX},
+    split::{self, RemainderPolicy},
+};
+use solana_sdk::pubkey::Pubkey;
+
+#[test]
+fn test_parse_split_events() {
+    let payer = Pubkey::new_unique();
+    let payees = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let distribution = split::distribute(1_001, &[1, 1], RemainderPolicy::Payer).unwrap();
+    let event = SplitEvent::new(42, &payer, None, &payees, &distribution, None, None);
+    assert_eq!(event.payees[0].payee, payees[0]);
+    assert_eq!(event.payees[1].amount, 500);
+    assert_eq!(event.remainder, 1);
+
+    // Only events logged by the split program itself are parsed
+    let program_id = Pubkey::new_unique();
+    let other_program = Pubkey::new_unique();
+    let spoofed = SplitEvent::new(42, &other_program, None, &payees, &distribution, None, None);
+    let logs = vec![
+        format!("Program {} invoke [1]", program_id),
+        "Program 11111111111111111111111111111111 invoke [2]".to_string(),
+        format!("Program log: {}", spoofed.to_log()),
+        "Program 11111111111111111111111111111111 success".to_string(),
+        "Program log: remainder of 1 lamports stays with the payer".to_string(),
+        format!("Program log: {}not base64!", EVENT_LOG_PREFIX),
+        format!("Program log: {}", event.to_log()),
+        format!(
+            "Program {} consumed 10000 of 200000 compute units",
+            program_id
+        ),
+        format!("Program {} success", program_id),
+        format!("Program {} invoke [1]", other_program),
+        format!("Program log: {}", spoofed.to_log()),
+        format!("Program {} success", other_program),
+        format!("Program {} invoke [1]", program_id),
+        format!("Program log: {}", event.to_log()),
+        format!("Program {} failed: custom program error: 0x0", program_id),
+        event.to_log(),
+    ];
+    assert_eq!(
+        parse_logs(&program_id, &logs),
+        vec![event.clone(), event.clone()]
+    );
+
+    // The id changes with the slot and the split id
+    let later = SplitEvent::new(43, &payer, None, &payees, &distribution, None, None);
+    assert_ne!(later.id, event.id);
+    let recorded = SplitEvent::new(
+        42,
+        &payer,
+        None,
+        &payees,
+        &distribution,
+        Some(&[1; 32]),
+        None,
+    );
+    assert_ne!(recorded.id, event.id);
+    assert_eq!(recorded.split_id, Some([1; 32]));
+
+    // Unknown versions are reported rather than misread
+    let mut data = event.pack();
+    data[0] = 0xff;
+    assert_eq!(
+        SplitEvent::unpack(&data).unwrap_err(),
+        SplitError::UnsupportedEventVersion
+    );
+    assert_eq!(
+        SplitEvent::from_log(&format!("{}not base64!", EVENT_LOG_PREFIX)).unwrap(),
+        Err(SplitError::InvalidEvent)
+    );
+
+    // Events logged before memos were added decode without one
+    let mut data = event.pack();
+    data[0] = 1;
+    data.truncate(data.len() - 2);
+    assert_eq!(SplitEvent::unpack(&data).unwrap(), event);
+
+    let memo = SplitEvent::new(
+        42,
+        &payer,
+        None,
+        &payees,
+        &distribution,
+        None,
+        Some("invoice 42"),
+    );
+    assert_eq!(
+        SplitEvent::from_log(&memo.to_log()).unwrap().unwrap().memo,
+        Some("invoice 42".to_string())
+    );
+}
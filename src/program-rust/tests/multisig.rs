@@ -0,0 +1,299 @@
+use helloworld::{
+    error::SplitError,
+    instruction::{
+        approve_proposal, close_proposal, create_multisig, create_proposal, multisig_split,
+    },
+    process_instruction,
+    split::{RemainderPolicy, Weights},
+    state::{Interval, Multisig, Proposal},
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    borsh::try_from_slice_unchecked,
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::{Transaction, TransactionError},
+};
+
+#[tokio::test]
+async fn test_multisig_splits() {
+    let program_id = Pubkey::new_unique();
+    let signers = [Keypair::new(), Keypair::new(), Keypair::new()];
+    let signer_keys: Vec<Pubkey> = signers.iter().map(|signer| signer.pubkey()).collect();
+    let payees = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    // The second signer pays for the proposal account
+    program_test.add_account(
+        signers[1].pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let (multisig, _) = Multisig::find_address(&program_id, &payer.pubkey(), 1);
+
+    // 2 of 3 signers for splits past 10,000,000 lamports a period
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            create_multisig(
+                &program_id,
+                &payer.pubkey(),
+                1,
+                signer_keys,
+                2,
+                10_000_000,
+                Interval::Slots(100),
+            ),
+            system_instruction::transfer(&payer.pubkey(), &multisig, 100_000_000),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // One signer is not enough for a large split
+    let split = |signers: &[Pubkey]| {
+        multisig_split(
+            &program_id,
+            &multisig,
+            &payees,
+            signers,
+            20_000_000,
+            Weights::Shares(vec![1, 1]),
+            RemainderPolicy::Payer,
+        )
+    };
+    let mut transaction =
+        Transaction::new_with_payer(&[split(&[signers[0].pubkey()])], Some(&payer.pubkey()));
+    transaction.sign(&[&payer, &signers[0]], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SplitError::NotEnoughSigners as u32)
+        )
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        &[split(&[signers[0].pubkey(), signers[2].pubkey()])],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &signers[0], &signers[2]], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    for payee in payees.iter() {
+        assert_eq!(banks_client.get_balance(*payee).await.unwrap(), 10_000_000);
+    }
+
+    // A proposal is executed by the approval that reaches the threshold
+    let (proposal, _) = Proposal::find_address(&program_id, &multisig, 0);
+    let mut transaction = Transaction::new_with_payer(
+        &[create_proposal(
+            &program_id,
+            &signers[1].pubkey(),
+            &multisig,
+            0,
+            20_000_000,
+            payees.clone(),
+            Weights::Shares(vec![3, 1]),
+            RemainderPolicy::Payer,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &signers[1]], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(
+        banks_client.get_balance(payees[0]).await.unwrap(),
+        10_000_000
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        &[approve_proposal(
+            &program_id,
+            &signers[2].pubkey(),
+            &multisig,
+            &proposal,
+            &payees,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &signers[2]], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(
+        banks_client.get_balance(payees[0]).await.unwrap(),
+        25_000_000
+    );
+    assert_eq!(
+        banks_client.get_balance(payees[1]).await.unwrap(),
+        15_000_000
+    );
+
+    let account = banks_client.get_account(proposal).await.unwrap().unwrap();
+    let executed: Proposal = try_from_slice_unchecked(&account.data).unwrap();
+    assert!(executed.executed);
+    assert_eq!(executed.proposer, signers[1].pubkey());
+
+    // Only the proposer can close the proposal and reclaim its rent
+    let close = |proposer: &Keypair| {
+        let mut transaction = Transaction::new_with_payer(
+            &[close_proposal(
+                &program_id,
+                &proposer.pubkey(),
+                &proposal,
+                &signers[1].pubkey(),
+            )],
+            Some(&payer.pubkey()),
+        );
+        transaction.sign(&[&payer, proposer], recent_blockhash);
+        transaction
+    };
+    assert_eq!(
+        banks_client
+            .process_transaction(close(&signers[0]))
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SplitError::InvalidAuthority as u32)
+        )
+    );
+    let balance = banks_client.get_balance(signers[1].pubkey()).await.unwrap();
+    banks_client
+        .process_transaction(close(&signers[1]))
+        .await
+        .unwrap();
+    assert_eq!(
+        banks_client.get_balance(signers[1].pubkey()).await.unwrap(),
+        balance + account.lamports
+    );
+}
+
+#[tokio::test]
+async fn test_multisig_spend_limit() {
+    let program_id = Pubkey::new_unique();
+    let signers = [Keypair::new(), Keypair::new()];
+    let signer_keys: Vec<Pubkey> = signers.iter().map(|signer| signer.pubkey()).collect();
+    let payees = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+    let (multisig, _) = Multisig::find_address(&program_id, &payer, 1);
+
+    // One signer may split 10,000,000 lamports every 100 slots
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            create_multisig(
+                &program_id,
+                &payer,
+                1,
+                signer_keys,
+                2,
+                10_000_000,
+                Interval::Slots(100),
+            ),
+            system_instruction::transfer(&payer, &multisig, 100_000_000),
+        ],
+        Some(&payer),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let split = |signers: &[Pubkey], amount| {
+        multisig_split(
+            &program_id,
+            &multisig,
+            &payees,
+            signers,
+            amount,
+            Weights::Shares(vec![1, 1]),
+            RemainderPolicy::Payer,
+        )
+    };
+    let single = signers[0].pubkey();
+    let both = [signers[0].pubkey(), signers[1].pubkey()];
+
+    // Repeated single-signer splits can't get past the limit
+    let mut transaction = Transaction::new_with_payer(&[split(&[single], 6_000_000)], Some(&payer));
+    transaction.sign(&[&context.payer, &signers[0]], context.last_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let mut transaction = Transaction::new_with_payer(&[split(&[single], 5_000_000)], Some(&payer));
+    transaction.sign(&[&context.payer, &signers[0]], context.last_blockhash);
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SplitError::NotEnoughSigners as u32)
+        )
+    );
+
+    // The threshold can still split, without using up the limit
+    let mut transaction = Transaction::new_with_payer(
+        &[split(&both, 5_000_000), split(&[single], 4_000_000)],
+        Some(&payer),
+    );
+    transaction.sign(
+        &[&context.payer, &signers[0], &signers[1]],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    for payee in payees.iter() {
+        assert_eq!(
+            context.banks_client.get_balance(*payee).await.unwrap(),
+            7_500_000
+        );
+    }
+
+    // The limit is available again in the next period
+    let account = context
+        .banks_client
+        .get_account(multisig)
+        .await
+        .unwrap()
+        .unwrap();
+    let state: Multisig = try_from_slice_unchecked(&account.data).unwrap();
+    assert_eq!(state.spent, 10_000_000);
+    context.warp_to_slot(state.period_start + 100).unwrap();
+    let recent_blockhash = context.banks_client.get_recent_blockhash().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(&[split(&[single], 5_000_000)], Some(&payer));
+    transaction.sign(&[&context.payer, &signers[0]], recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    for payee in payees.iter() {
+        assert_eq!(
+            context.banks_client.get_balance(*payee).await.unwrap(),
+            10_000_000
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use helloworld::{
+    error::SplitError,
+    instruction::{close_receipt_log, create_receipt_log, split, weighted_split, with_receipt_log},
+    process_instruction,
+    split::{DuplicatePolicy, DustPolicy, MemoPolicy, RemainderPolicy, Weights},
+    state::ReceiptLog,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    borsh::try_from_slice_unchecked, pubkey::Pubkey, signature::Signer, transaction::Transaction,
+};
+
+#[tokio::test]
+async fn test_receipt_log() {
+    let program_id = Pubkey::new_unique();
+    let payees = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let (receipt_log, _) = ReceiptLog::find_address(&program_id, &payer.pubkey());
+
+    // Three splits into a log holding two receipts overwrite the first
+    let mut instructions = vec![create_receipt_log(&program_id, &payer.pubkey(), 2)];
+    for amount in [10_000_000, 20_000_000].iter() {
+        instructions.push(with_receipt_log(
+            split(&program_id, &payer.pubkey(), &payees, *amount),
+            &program_id,
+            &payer.pubkey(),
+        ));
+    }
+    instructions.push(with_receipt_log(
+        weighted_split(
+            &program_id,
+            &payer.pubkey(),
+            &payees,
+            40_000_000,
+            Weights::Shares(vec![3, 1]),
+            RemainderPolicy::Payer,
+            DuplicatePolicy::Reject,
+            DustPolicy::Allow,
+            None,
+            MemoPolicy::None,
+        ),
+        &program_id,
+        &payer.pubkey(),
+    ));
+    let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer.pubkey()));
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let account = banks_client
+        .get_account(receipt_log)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut log: ReceiptLog = try_from_slice_unchecked(&account.data).unwrap();
+    assert_eq!(log.count, 3);
+    log.verify().unwrap();
+    let history = log.history();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].amount, 20_000_000);
+    assert_eq!(history[1].payees, payees.to_vec());
+    assert_eq!(history[1].shares, vec![30_000_000, 10_000_000]);
+
+    // Rewriting a receipt breaks the chain
+    log.receipts[0].shares[0] += 1;
+    assert_eq!(log.verify().unwrap_err(), SplitError::BrokenReceiptChain);
+
+    let destination = Pubkey::new_unique();
+    let mut transaction = Transaction::new_with_payer(
+        &[close_receipt_log(
+            &program_id,
+            &payer.pubkey(),
+            &destination,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    assert!(banks_client
+        .get_account(receipt_log)
+        .await
+        .unwrap()
+        .is_none());
+}
@@ -0,0 +1,855 @@
+use helloworld::{
+    error::SplitError,
+    instruction::{self, SplitInstruction},
+    process_instruction,
+    split::{self, DuplicatePolicy, DustPolicy, MemoPolicy, RemainderPolicy, Weights},
+    state::SplitRecord,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    account::Account,
+    borsh::try_from_slice_unchecked,
+    instruction::{AccountMeta, Instruction, InstructionError},
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_program,
+    transaction::{Transaction, TransactionError},
+};
+
+fn split_instruction(
+    program_id: Pubkey,
+    payer: Pubkey,
+    payees: &[Pubkey],
+    data: Vec<u8>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(payer, true),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(payees.iter().map(|payee| AccountMeta::new(*payee, false)));
+    Instruction {
+        program_id,
+        accounts,
+        data,
+    }
+}
+
+#[tokio::test]
+async fn test_legacy_payload() {
+    let program_id = Pubkey::new_unique();
+    let payees = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+
+    let program_test = ProgramTest::new(
+        "helloworld", // Run the BPF version with `cargo test-bpf`
+        program_id,
+        processor!(process_instruction), // Run the native version with `cargo test`
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    // The original client sends the amount as 8 raw little-endian bytes
+    let mut transaction = Transaction::new_with_payer(
+        &[split_instruction(
+            program_id,
+            payer.pubkey(),
+            &payees,
+            3_000_u64.to_le_bytes().to_vec(),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    for payee in payees.iter() {
+        assert_eq!(banks_client.get_balance(*payee).await.unwrap(), 1_000);
+    }
+}
+
+#[tokio::test]
+async fn test_legacy_payer_and_duplicate_payees() {
+    let program_id = Pubkey::new_unique();
+    let funder = Keypair::new();
+    let payees = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        funder.pubkey(),
+        Account {
+            lamports: 1_000_000,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    // Like the original program, every listing is paid, and the payer's
+    // own share is a transfer to itself
+    let listed = [payees[0], payees[0], payees[1], funder.pubkey()];
+    let mut transaction = Transaction::new_with_payer(
+        &[split_instruction(
+            program_id,
+            funder.pubkey(),
+            &listed,
+            40_u64.to_le_bytes().to_vec(),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &funder], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(banks_client.get_balance(payees[0]).await.unwrap(), 20);
+    assert_eq!(banks_client.get_balance(payees[1]).await.unwrap(), 10);
+    assert_eq!(
+        banks_client.get_balance(funder.pubkey()).await.unwrap(),
+        1_000_000 - 30
+    );
+
+    // The tagged split rejects the payer as a payee
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::split(
+            &program_id,
+            &funder.pubkey(),
+            &[payees[1], funder.pubkey()],
+            40,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &funder], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SplitError::PayerIsPayee as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_tagged_split() {
+    let program_id = Pubkey::new_unique();
+    let payees = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::split(
+            &program_id,
+            &payer.pubkey(),
+            &payees,
+            5_000,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    for payee in payees.iter() {
+        assert_eq!(banks_client.get_balance(*payee).await.unwrap(), 2_500);
+    }
+}
+
+#[tokio::test]
+async fn test_weighted_split() {
+    let program_id = Pubkey::new_unique();
+    let payees = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let mut transaction = Transaction::new_with_payer(
+        &[split_instruction(
+            program_id,
+            payer.pubkey(),
+            &payees,
+            SplitInstruction::WeightedSplit {
+                amount: 10_000,
+                weights: Weights::BasisPoints(vec![6_000, 2_500, 1_500]),
+                remainder: RemainderPolicy::RejectUneven,
+                duplicates: DuplicatePolicy::Reject,
+                dust: DustPolicy::Allow,
+                split_id: None,
+                memo: MemoPolicy::None,
+            }
+            .pack(),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(banks_client.get_balance(payees[0]).await.unwrap(), 6_000);
+    assert_eq!(banks_client.get_balance(payees[1]).await.unwrap(), 2_500);
+    assert_eq!(banks_client.get_balance(payees[2]).await.unwrap(), 1_500);
+
+    // Basis points that don't add up to 10,000 are rejected
+    let mut transaction = Transaction::new_with_payer(
+        &[split_instruction(
+            program_id,
+            payer.pubkey(),
+            &payees,
+            SplitInstruction::WeightedSplit {
+                amount: 10_000,
+                weights: Weights::BasisPoints(vec![6_000, 2_500, 1_000]),
+                remainder: RemainderPolicy::RejectUneven,
+                duplicates: DuplicatePolicy::Reject,
+                dust: DustPolicy::Allow,
+                split_id: None,
+                memo: MemoPolicy::None,
+            }
+            .pack(),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SplitError::InvalidBasisPoints as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_remainder_policy() {
+    let program_id = Pubkey::new_unique();
+    let payees = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    // 10 lamports don't divide three ways, so rejecting uneven splits fails
+    let mut transaction = Transaction::new_with_payer(
+        &[split_instruction(
+            program_id,
+            payer.pubkey(),
+            &payees,
+            SplitInstruction::Split {
+                amount: 10,
+                remainder: RemainderPolicy::RejectUneven,
+                duplicates: DuplicatePolicy::Reject,
+                dust: DustPolicy::Allow,
+                split_id: None,
+                memo: MemoPolicy::None,
+            }
+            .pack(),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SplitError::UnevenSplit as u32)
+        )
+    );
+
+    // The last payee picks up the leftover lamport
+    let mut transaction = Transaction::new_with_payer(
+        &[split_instruction(
+            program_id,
+            payer.pubkey(),
+            &payees,
+            SplitInstruction::Split {
+                amount: 10,
+                remainder: RemainderPolicy::LastPayee,
+                duplicates: DuplicatePolicy::Reject,
+                dust: DustPolicy::Allow,
+                split_id: None,
+                memo: MemoPolicy::None,
+            }
+            .pack(),
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(banks_client.get_balance(payees[0]).await.unwrap(), 3);
+    assert_eq!(banks_client.get_balance(payees[1]).await.unwrap(), 3);
+    assert_eq!(banks_client.get_balance(payees[2]).await.unwrap(), 4);
+}
+
+#[tokio::test]
+async fn test_duplicate_payees() {
+    let program_id = Pubkey::new_unique();
+    let payee = Pubkey::new_unique();
+    let other = Pubkey::new_unique();
+    let payees = [payee, other, payee];
+
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    // A payee listed twice is rejected by default
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::split(
+            &program_id,
+            &payer.pubkey(),
+            &payees,
+            900,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SplitError::DuplicatePayee as u32)
+        )
+    );
+
+    // Merging pays the repeated payee both of its shares
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::split_with_options(
+            &program_id,
+            &payer.pubkey(),
+            &payees,
+            900,
+            RemainderPolicy::Payer,
+            DuplicatePolicy::Merge,
+            DustPolicy::Allow,
+            None,
+            MemoPolicy::None,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(banks_client.get_balance(payee).await.unwrap(), 600);
+    assert_eq!(banks_client.get_balance(other).await.unwrap(), 300);
+
+    // The payer can't pay itself, whatever the duplicate policy
+    let mut transaction = Transaction::new_with_payer(
+        &[instruction::split_with_options(
+            &program_id,
+            &payer.pubkey(),
+            &[other, payer.pubkey()],
+            900,
+            RemainderPolicy::Payer,
+            DuplicatePolicy::Merge,
+            DustPolicy::Allow,
+            None,
+            MemoPolicy::None,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SplitError::PayerIsPayee as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_dust_policy() {
+    let program_id = Pubkey::new_unique();
+
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let minimum = banks_client.get_rent().await.unwrap().minimum_balance(0);
+
+    // A 90/10 split of 2 rent-exempt minimums leaves the second payee short
+    let amount = 2 * minimum;
+    let dust_split = |payees: &[Pubkey], dust| {
+        instruction::weighted_split(
+            &program_id,
+            &payer.pubkey(),
+            payees,
+            amount,
+            Weights::BasisPoints(vec![9_000, 1_000]),
+            RemainderPolicy::FirstPayee,
+            DuplicatePolicy::Reject,
+            dust,
+            None,
+            MemoPolicy::None,
+        )
+    };
+
+    let payees = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let mut transaction = Transaction::new_with_payer(
+        &[dust_split(&payees, DustPolicy::Reject)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SplitError::DustShare as u32)
+        )
+    );
+
+    // Refunding skips the dust share
+    let mut transaction = Transaction::new_with_payer(
+        &[dust_split(&payees, DustPolicy::Refund)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(
+        banks_client.get_balance(payees[0]).await.unwrap(),
+        amount - amount / 10
+    );
+    assert_eq!(banks_client.get_balance(payees[1]).await.unwrap(), 0);
+
+    // Redistributing hands the dust share to the other payee
+    let payees = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let mut transaction = Transaction::new_with_payer(
+        &[dust_split(&payees, DustPolicy::Redistribute)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(banks_client.get_balance(payees[0]).await.unwrap(), amount);
+    assert_eq!(banks_client.get_balance(payees[1]).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_split_id() {
+    let program_id = Pubkey::new_unique();
+    let payees = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let retry = |amount| {
+        instruction::split_with_options(
+            &program_id,
+            &payer.pubkey(),
+            &payees,
+            amount,
+            RemainderPolicy::Payer,
+            DuplicatePolicy::Reject,
+            DustPolicy::Allow,
+            Some([1; 32]),
+            MemoPolicy::None,
+        )
+    };
+
+    let mut transaction = Transaction::new_with_payer(&[retry(10_000_000)], Some(&payer.pubkey()));
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    // A retry with the same split id isn't paid again
+    let mut transaction = Transaction::new_with_payer(&[retry(10_000_001)], Some(&payer.pubkey()));
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SplitError::SplitIdUsed as u32)
+        )
+    );
+    for payee in payees.iter() {
+        assert_eq!(banks_client.get_balance(*payee).await.unwrap(), 5_000_000);
+    }
+}
+
+#[tokio::test]
+async fn test_prefunded_split_record() {
+    let program_id = Pubkey::new_unique();
+    let payees = [Pubkey::new_unique(), Pubkey::new_unique()];
+    let funder = Keypair::new();
+    let (record, _) = SplitRecord::find_address(&program_id, &funder.pubkey(), &[1; 32]);
+
+    // Anyone can send lamports to the record's address before the split
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        funder.pubkey(),
+        Account {
+            lamports: 100_000_000,
+            ..Account::default()
+        },
+    );
+    program_test.add_account(
+        record,
+        Account {
+            lamports: 1,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let split = |amount| {
+        instruction::split_with_options(
+            &program_id,
+            &funder.pubkey(),
+            &payees,
+            amount,
+            RemainderPolicy::Payer,
+            DuplicatePolicy::Reject,
+            DustPolicy::Allow,
+            Some([1; 32]),
+            MemoPolicy::None,
+        )
+    };
+
+    let mut transaction = Transaction::new_with_payer(&[split(10_000_000)], Some(&payer.pubkey()));
+    transaction.sign(&[&payer, &funder], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    for payee in payees.iter() {
+        assert_eq!(banks_client.get_balance(*payee).await.unwrap(), 5_000_000);
+    }
+
+    // The record was topped up to rent exemption rather than created
+    let account = banks_client.get_account(record).await.unwrap().unwrap();
+    assert_eq!(account.owner, program_id);
+    assert_eq!(
+        account.lamports,
+        Rent::default().minimum_balance(account.data.len())
+    );
+    let state: SplitRecord = try_from_slice_unchecked(&account.data).unwrap();
+    assert_eq!(state.split_id, [1; 32]);
+    assert_eq!(
+        banks_client.get_balance(funder.pubkey()).await.unwrap(),
+        100_000_000 - 10_000_000 - (account.lamports - 1)
+    );
+
+    let mut transaction = Transaction::new_with_payer(&[split(10_000_001)], Some(&payer.pubkey()));
+    transaction.sign(&[&payer, &funder], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SplitError::SplitIdUsed as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_memo() {
+    let program_id = Pubkey::new_unique();
+    let payees = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let memo_split = |memo| {
+        instruction::split_with_options(
+            &program_id,
+            &payer.pubkey(),
+            &payees,
+            10_000_000,
+            RemainderPolicy::Payer,
+            DuplicatePolicy::Reject,
+            DustPolicy::Allow,
+            None,
+            memo,
+        )
+    };
+
+    // A memo from the transaction must be in an SPL Memo instruction
+    let mut transaction = Transaction::new_with_payer(
+        &[memo_split(MemoPolicy::Transaction)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SplitError::MissingMemo as u32)
+        )
+    );
+
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            spl_memo::build_memo(b"invoice 42", &[]),
+            memo_split(MemoPolicy::Transaction),
+            memo_split(MemoPolicy::Inline("invoice 43".to_string())),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    for payee in payees.iter() {
+        assert_eq!(banks_client.get_balance(*payee).await.unwrap(), 10_000_000);
+    }
+
+    let mut transaction = Transaction::new_with_payer(
+        &[memo_split(MemoPolicy::Inline(String::new()))],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SplitError::InvalidMemo as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_program_owned_payer() {
+    let program_id = Pubkey::new_unique();
+    let payees = [Pubkey::new_unique(), Pubkey::new_unique()];
+    // The system program can't debit an account this program owns, so the
+    // split must move its lamports directly
+    let funder = Keypair::new();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_account(
+        funder.pubkey(),
+        Account {
+            lamports: 100_000_000,
+            owner: program_id,
+            ..Account::default()
+        },
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let minimum = banks_client.get_rent().await.unwrap().minimum_balance(0);
+    let funder_split = |amount, split_id| {
+        instruction::split_with_options(
+            &program_id,
+            &funder.pubkey(),
+            &payees,
+            amount,
+            RemainderPolicy::Payer,
+            DuplicatePolicy::Reject,
+            DustPolicy::Allow,
+            split_id,
+            MemoPolicy::None,
+        )
+    };
+
+    let mut transaction =
+        Transaction::new_with_payer(&[funder_split(20_000_001, None)], Some(&payer.pubkey()));
+    transaction.sign(&[&payer, &funder], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    for payee in payees.iter() {
+        assert_eq!(banks_client.get_balance(*payee).await.unwrap(), 10_000_000);
+    }
+    assert_eq!(
+        banks_client.get_balance(funder.pubkey()).await.unwrap(),
+        80_000_000
+    );
+
+    // The funder must stay rent exempt
+    let mut transaction = Transaction::new_with_payer(
+        &[funder_split(80_000_002 - minimum, None)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &funder], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(0, InstructionError::InsufficientFunds)
+    );
+
+    // The system program can't fund a split record from the funder either
+    let mut transaction = Transaction::new_with_payer(
+        &[funder_split(20_000_000, Some([7; 32]))],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &funder], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SplitError::ProgramOwnedPayerSplitId as u32)
+        )
+    );
+}
+
+#[tokio::test]
+async fn test_quote() {
+    let program_id = Pubkey::new_unique();
+    let payees = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let minimum = banks_client.get_rent().await.unwrap().minimum_balance(0);
+    let quote = |dust| {
+        instruction::quote(
+            &program_id,
+            &payer.pubkey(),
+            &payees,
+            2 * minimum,
+            Some(Weights::BasisPoints(vec![9_000, 1_000])),
+            RemainderPolicy::FirstPayee,
+            DuplicatePolicy::Reject,
+            dust,
+        )
+    };
+
+    // A quote fails the way the split would
+    let mut transaction =
+        Transaction::new_with_payer(&[quote(DustPolicy::Reject)], Some(&payer.pubkey()));
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SplitError::DustShare as u32)
+        )
+    );
+
+    // and moves nothing when it succeeds
+    let mut transaction =
+        Transaction::new_with_payer(&[quote(DustPolicy::Refund)], Some(&payer.pubkey()));
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    for payee in payees.iter() {
+        assert_eq!(banks_client.get_balance(*payee).await.unwrap(), 0);
+    }
+}
+
+#[test]
+fn test_plan_distribution() {
+    let payer = Pubkey::new_unique();
+    let payees = [
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+        Pubkey::new_unique(),
+    ];
+
+    // The repeated payee is merged and the short one's share refunded
+    let plan = split::plan_distribution(
+        &payer,
+        &[payees[0], payees[1], payees[0], payees[2]],
+        1_000,
+        &Weights::Shares(vec![1, 1, 1, 1]),
+        RemainderPolicy::Payer,
+        DuplicatePolicy::Merge,
+        &[0, 0, 0, 500],
+        DustPolicy::Refund,
+    )
+    .unwrap();
+    assert_eq!(plan.indices, vec![0, 1, 3]);
+    assert_eq!(plan.distribution.amounts, vec![500, 250, 0]);
+    assert_eq!(plan.dust.len(), 1);
+    assert_eq!(plan.dust[0].index, 3);
+
+    assert_eq!(
+        split::plan_distribution(
+            &payer,
+            &payees,
+            1_000,
+            &Weights::Shares(vec![1, 1]),
+            RemainderPolicy::Payer,
+            DuplicatePolicy::Reject,
+            &[0, 0, 0],
+            DustPolicy::Allow,
+        )
+        .unwrap_err(),
+        SplitError::WeightCountMismatch.into()
+    );
+}
+
+#[test]
+fn test_remainder_skips_zero_weights() {
+    let distribution = split::distribute(10, &[0, 1, 1, 0], RemainderPolicy::FirstPayee).unwrap();
+    assert_eq!(distribution.amounts, vec![0, 5, 5, 0]);
+
+    let distribution = split::distribute(11, &[0, 1, 1, 0], RemainderPolicy::FirstPayee).unwrap();
+    assert_eq!(distribution.amounts, vec![0, 6, 5, 0]);
+
+    let distribution = split::distribute(11, &[0, 1, 1, 0], RemainderPolicy::LastPayee).unwrap();
+    assert_eq!(distribution.amounts, vec![0, 5, 6, 0]);
+}
+
+#[test]
+fn test_unpack_rejects_unknown_version() {
+    let mut data = SplitInstruction::Split {
+        amount: 1,
+        remainder: RemainderPolicy::Payer,
+        duplicates: DuplicatePolicy::Reject,
+        dust: DustPolicy::Allow,
+        split_id: None,
+        memo: MemoPolicy::None,
+    }
+    .pack();
+    // Too short to be a legacy amount
+    data.truncate(4);
+    data[0] = 0xff;
+    assert_eq!(
+        SplitInstruction::unpack(&data).unwrap_err(),
+        SplitError::UnsupportedVersion.into()
+    );
+    assert_eq!(
+        SplitInstruction::unpack(&7_u64.to_le_bytes()).unwrap(),
+        SplitInstruction::Legacy { amount: 7 }
+    );
+}
+
+#[test]
+fn test_decode_error_codes() {
+    let error: ProgramError = SplitError::InvalidPayeeCount.into();
+    assert_eq!(
+        SplitError::from_program_error(&error),
+        Some(SplitError::InvalidPayeeCount)
+    );
+    assert_eq!(SplitError::from_code(0x107), Some(SplitError::UnevenSplit));
+    // System program errors surfaced through a transfer are not split errors
+    assert_eq!(SplitError::from_code(1), None);
+}
@@ -0,0 +1,157 @@
+use helloworld::{
+    error::SplitError,
+    instruction::{
+        close_split_group, create_payee_ledger, create_split_group, execute_split_group,
+        with_payee_ledgers,
+    },
+    process_instruction,
+    split::{DustPolicy, RemainderPolicy, Weights},
+    state::{PayeeLedger, ProgramAccount, SplitGroup},
+};
+use solana_program_test::*;
+use solana_sdk::{
+    borsh::try_from_slice_unchecked,
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::Signer,
+    transaction::{Transaction, TransactionError},
+};
+
+#[tokio::test]
+async fn test_split_group_lifecycle() {
+    let program_id = Pubkey::new_unique();
+    let recipients = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let (group, _) = SplitGroup::find_address(&program_id, &payer.pubkey(), 1);
+
+    // Create a 75/25 group
+    let mut transaction = Transaction::new_with_payer(
+        &[create_split_group(
+            &program_id,
+            &payer.pubkey(),
+            1,
+            recipients.clone(),
+            Weights::BasisPoints(vec![7_500, 2_500]),
+            RemainderPolicy::FirstPayee,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let group_account = banks_client.get_account(group).await.unwrap().unwrap();
+    assert_eq!(group_account.owner, program_id);
+    assert_eq!(group_account.data.len(), SplitGroup::LEN);
+
+    // Execute the stored split
+    let execute = |payees: &[Pubkey]| {
+        execute_split_group(
+            &program_id,
+            &payer.pubkey(),
+            &group,
+            payees,
+            1_001,
+            DustPolicy::Allow,
+        )
+    };
+    let mut transaction =
+        Transaction::new_with_payer(&[execute(&recipients)], Some(&payer.pubkey()));
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(banks_client.get_balance(recipients[0]).await.unwrap(), 751);
+    assert_eq!(banks_client.get_balance(recipients[1]).await.unwrap(), 250);
+
+    // Payees must match the stored recipients
+    let mut transaction = Transaction::new_with_payer(
+        &[execute(&[recipients[1], recipients[0]])],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SplitError::PayeeMismatch as u32)
+        )
+    );
+
+    // Close the group and reclaim its rent
+    let destination = Pubkey::new_unique();
+    let mut transaction = Transaction::new_with_payer(
+        &[close_split_group(
+            &program_id,
+            &payer.pubkey(),
+            &group,
+            &destination,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    assert!(banks_client.get_account(group).await.unwrap().is_none());
+    assert_eq!(
+        banks_client.get_balance(destination).await.unwrap(),
+        group_account.lamports
+    );
+}
+
+#[tokio::test]
+async fn test_payee_ledger() {
+    let program_id = Pubkey::new_unique();
+    let recipients = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let (group, _) = SplitGroup::find_address(&program_id, &payer.pubkey(), 1);
+    let (ledger, _) = PayeeLedger::find_address(&program_id, &group, &recipients[1]);
+
+    // Only the second recipient keeps a ledger
+    let execute = with_payee_ledgers(
+        execute_split_group(
+            &program_id,
+            &payer.pubkey(),
+            &group,
+            &recipients,
+            10_000_000,
+            DustPolicy::Allow,
+        ),
+        &program_id,
+        &group,
+        &recipients[1..],
+    );
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            create_split_group(
+                &program_id,
+                &payer.pubkey(),
+                1,
+                recipients.clone(),
+                Weights::BasisPoints(vec![7_500, 2_500]),
+                RemainderPolicy::FirstPayee,
+            ),
+            create_payee_ledger(&program_id, &payer.pubkey(), &group, &recipients[1]),
+            execute.clone(),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let mut transaction = Transaction::new_with_payer(&[execute], Some(&payer.pubkey()));
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let account = banks_client.get_account(ledger).await.unwrap().unwrap();
+    let state: PayeeLedger = try_from_slice_unchecked(&account.data).unwrap();
+    assert_eq!(state.payee, recipients[1]);
+    assert_eq!(state.total_received, 5_000_000);
+    assert_eq!(state.split_count, 2);
+}
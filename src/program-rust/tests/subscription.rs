@@ -0,0 +1,144 @@
+use helloworld::{
+    error::SplitError,
+    instruction::{crank, create_subscription, refund_subscription},
+    process_instruction,
+    split::{RemainderPolicy, Weights},
+    state::{Interval, ProgramAccount, Subscription},
+};
+use solana_program_test::*;
+use solana_sdk::{
+    borsh::try_from_slice_unchecked,
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::Signer,
+    transaction::{Transaction, TransactionError},
+};
+
+#[tokio::test]
+async fn test_subscription_crank() {
+    let program_id = Pubkey::new_unique();
+    let recipients = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let mut context = program_test.start_with_context().await;
+    let payer = context.payer.pubkey();
+    let (subscription, _) = Subscription::find_address(&program_id, &payer, 1);
+    let rent = context
+        .banks_client
+        .get_rent()
+        .await
+        .unwrap()
+        .minimum_balance(Subscription::LEN);
+
+    // Pay 10,000,000 lamports 50/50 every 100 slots
+    let mut transaction = Transaction::new_with_payer(
+        &[create_subscription(
+            &program_id,
+            &payer,
+            1,
+            10_000_000,
+            Interval::Slots(100),
+            20_000_000,
+            recipients.clone(),
+            Weights::Shares(vec![1, 1]),
+            RemainderPolicy::Payer,
+        )],
+        Some(&payer),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    let account = context
+        .banks_client
+        .get_account(subscription)
+        .await
+        .unwrap()
+        .unwrap();
+    let state: Subscription = try_from_slice_unchecked(&account.data).unwrap();
+
+    // Cranking before the first payment is due fails
+    let mut transaction = Transaction::new_with_payer(
+        &[crank(&program_id, &subscription, &recipients)],
+        Some(&payer),
+    );
+    transaction.sign(&[&context.payer], context.last_blockhash);
+    assert_eq!(
+        context
+            .banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SplitError::PaymentNotDue as u32)
+        )
+    );
+
+    context.warp_to_slot(state.next_due).unwrap();
+    let recent_blockhash = context.banks_client.get_recent_blockhash().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(
+        &[crank(&program_id, &subscription, &recipients)],
+        Some(&payer),
+    );
+    transaction.sign(&[&context.payer], recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    for recipient in recipients.iter() {
+        assert_eq!(
+            context.banks_client.get_balance(*recipient).await.unwrap(),
+            5_000_000
+        );
+    }
+    let account = context
+        .banks_client
+        .get_account(subscription)
+        .await
+        .unwrap()
+        .unwrap();
+    let cranked: Subscription = try_from_slice_unchecked(&account.data).unwrap();
+    assert_eq!(cranked.next_due, state.next_due + 100);
+
+    // Refunding a quarter of the way through the next period pays a quarter
+    context.warp_to_slot(state.next_due + 25).unwrap();
+    let recent_blockhash = context.banks_client.get_recent_blockhash().await.unwrap();
+    let destination = Pubkey::new_unique();
+    let mut transaction = Transaction::new_with_payer(
+        &[refund_subscription(
+            &program_id,
+            &payer,
+            &subscription,
+            &destination,
+            &recipients,
+        )],
+        Some(&payer),
+    );
+    transaction.sign(&[&context.payer], recent_blockhash);
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    for recipient in recipients.iter() {
+        assert_eq!(
+            context.banks_client.get_balance(*recipient).await.unwrap(),
+            6_250_000
+        );
+    }
+    assert_eq!(
+        context.banks_client.get_balance(destination).await.unwrap(),
+        rent + 20_000_000 - 12_500_000
+    );
+    assert!(context
+        .banks_client
+        .get_account(subscription)
+        .await
+        .unwrap()
+        .is_none());
+}
@@ -0,0 +1,178 @@
+use helloworld::{
+    instruction::token_split,
+    process_instruction,
+    split::{DuplicatePolicy, RemainderPolicy, Weights},
+};
+use solana_program_test::*;
+use solana_sdk::{
+    hash::Hash,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+use spl_token::state::{Account as TokenAccount, Mint};
+
+const DECIMALS: u8 = 6;
+
+async fn create_mint(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+    mint: &Keypair,
+) {
+    let rent = banks_client.get_rent().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &mint.pubkey(),
+                rent.minimum_balance(Mint::LEN),
+                Mint::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_mint(
+                &spl_token::id(),
+                &mint.pubkey(),
+                &payer.pubkey(),
+                None,
+                DECIMALS,
+            )
+            .unwrap(),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, mint], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+}
+
+async fn create_token_account(
+    banks_client: &mut BanksClient,
+    payer: &Keypair,
+    recent_blockhash: Hash,
+    mint: &Pubkey,
+    owner: &Pubkey,
+) -> Pubkey {
+    let account = Keypair::new();
+    let rent = banks_client.get_rent().await.unwrap();
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &account.pubkey(),
+                rent.minimum_balance(TokenAccount::LEN),
+                TokenAccount::LEN as u64,
+                &spl_token::id(),
+            ),
+            spl_token::instruction::initialize_account(
+                &spl_token::id(),
+                &account.pubkey(),
+                mint,
+                owner,
+            )
+            .unwrap(),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[payer, &account], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    account.pubkey()
+}
+
+async fn token_balance(banks_client: &mut BanksClient, account: Pubkey) -> u64 {
+    let account = banks_client.get_account(account).await.unwrap().unwrap();
+    TokenAccount::unpack(&account.data).unwrap().amount
+}
+
+#[tokio::test]
+async fn test_token_split() {
+    let program_id = Pubkey::new_unique();
+    let mint = Keypair::new();
+
+    let mut program_test =
+        ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    program_test.add_program(
+        "spl_token",
+        spl_token::id(),
+        processor!(spl_token::processor::Processor::process),
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    create_mint(&mut banks_client, &payer, recent_blockhash, &mint).await;
+    let source = create_token_account(
+        &mut banks_client,
+        &payer,
+        recent_blockhash,
+        &mint.pubkey(),
+        &payer.pubkey(),
+    )
+    .await;
+    let mut payees = vec![];
+    for _ in 0..3 {
+        payees.push(
+            create_token_account(
+                &mut banks_client,
+                &payer,
+                recent_blockhash,
+                &mint.pubkey(),
+                &Pubkey::new_unique(),
+            )
+            .await,
+        );
+    }
+
+    // Mint revenue and split it 60/25/15
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            spl_token::instruction::mint_to(
+                &spl_token::id(),
+                &mint.pubkey(),
+                &source,
+                &payer.pubkey(),
+                &[],
+                1_000_001,
+            )
+            .unwrap(),
+            token_split(
+                &program_id,
+                &source,
+                &mint.pubkey(),
+                &payer.pubkey(),
+                &payees,
+                1_000_001,
+                DECIMALS,
+                Some(Weights::BasisPoints(vec![6_000, 2_500, 1_500])),
+                RemainderPolicy::LastPayee,
+                DuplicatePolicy::Reject,
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(token_balance(&mut banks_client, payees[0]).await, 600_000);
+    assert_eq!(token_balance(&mut banks_client, payees[1]).await, 250_000);
+    assert_eq!(token_balance(&mut banks_client, payees[2]).await, 150_001);
+    assert_eq!(token_balance(&mut banks_client, source).await, 0);
+
+    // The token program rejects the wrong decimals
+    let mut transaction = Transaction::new_with_payer(
+        &[token_split(
+            &program_id,
+            &source,
+            &mint.pubkey(),
+            &payer.pubkey(),
+            &payees,
+            0,
+            DECIMALS + 1,
+            None,
+            RemainderPolicy::Payer,
+            DuplicatePolicy::Reject,
+        )],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    assert!(banks_client.process_transaction(transaction).await.is_err());
+}
@@ -0,0 +1,48 @@
+use helloworld::{
+    instruction::vault_split,
+    process_instruction,
+    split::{DuplicatePolicy, DustPolicy, RemainderPolicy, Weights},
+    state::find_vault_address,
+};
+use solana_program_test::*;
+use solana_sdk::{pubkey::Pubkey, signature::Signer, system_instruction, transaction::Transaction};
+
+#[tokio::test]
+async fn test_vault_split() {
+    let program_id = Pubkey::new_unique();
+    let payees = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let (vault, _) = find_vault_address(&program_id, &payer.pubkey());
+
+    // Deposit into the vault, then split from it without the vault signing
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            system_instruction::transfer(&payer.pubkey(), &vault, 50_000_000),
+            vault_split(
+                &program_id,
+                &payer.pubkey(),
+                &payees,
+                40_000_000,
+                Weights::BasisPoints(vec![7_500, 2_500]),
+                RemainderPolicy::RejectUneven,
+                DuplicatePolicy::Reject,
+                DustPolicy::Reject,
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    assert_eq!(
+        banks_client.get_balance(payees[0]).await.unwrap(),
+        30_000_000
+    );
+    assert_eq!(
+        banks_client.get_balance(payees[1]).await.unwrap(),
+        10_000_000
+    );
+    assert_eq!(banks_client.get_balance(vault).await.unwrap(), 10_000_000);
+}
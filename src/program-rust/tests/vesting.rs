@@ -0,0 +1,109 @@
+use helloworld::{
+    error::SplitError,
+    instruction::{claim_vested, close_vesting, create_vesting, revoke_vesting},
+    process_instruction,
+    split::{RemainderPolicy, Weights},
+    state::Vesting,
+};
+use solana_program_test::*;
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+#[tokio::test]
+async fn test_vesting_claim_and_revoke() {
+    let program_id = Pubkey::new_unique();
+    let recipients = [Keypair::new(), Keypair::new()];
+    let recipient_keys: Vec<Pubkey> = recipients.iter().map(|r| r.pubkey()).collect();
+
+    let program_test = ProgramTest::new("helloworld", program_id, processor!(process_instruction));
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+    let now = banks_client.get_clock().await.unwrap().unix_timestamp;
+    let (vested, _) = Vesting::find_address(&program_id, &payer.pubkey(), 1);
+    let (locked, _) = Vesting::find_address(&program_id, &payer.pubkey(), 2);
+
+    // One schedule has fully vested, the other hasn't started
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            create_vesting(
+                &program_id,
+                &payer.pubkey(),
+                1,
+                20_000_000,
+                now - 10_000,
+                100,
+                1_000,
+                recipient_keys.clone(),
+                Weights::BasisPoints(vec![7_500, 2_500]),
+                RemainderPolicy::RejectUneven,
+            ),
+            create_vesting(
+                &program_id,
+                &payer.pubkey(),
+                2,
+                20_000_000,
+                now + 100_000,
+                100,
+                1_000,
+                recipient_keys.clone(),
+                Weights::BasisPoints(vec![7_500, 2_500]),
+                RemainderPolicy::RejectUneven,
+            ),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+
+    let mut transaction = Transaction::new_with_payer(
+        &[claim_vested(&program_id, &recipients[0].pubkey(), &vested)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &recipients[0]], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(
+        banks_client
+            .get_balance(recipients[0].pubkey())
+            .await
+            .unwrap(),
+        15_000_000
+    );
+
+    // Nothing can be claimed before the cliff
+    let mut transaction = Transaction::new_with_payer(
+        &[claim_vested(&program_id, &recipients[0].pubkey(), &locked)],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer, &recipients[0]], recent_blockhash);
+    assert_eq!(
+        banks_client
+            .process_transaction(transaction)
+            .await
+            .unwrap_err()
+            .unwrap(),
+        TransactionError::InstructionError(
+            0,
+            InstructionError::Custom(SplitError::NothingToWithdraw as u32)
+        )
+    );
+
+    // Revoking returns everything unvested, leaving nothing to claim
+    let destination = Pubkey::new_unique();
+    let mut transaction = Transaction::new_with_payer(
+        &[
+            revoke_vesting(&program_id, &payer.pubkey(), &locked, &destination),
+            close_vesting(&program_id, &payer.pubkey(), &locked, &payer.pubkey()),
+        ],
+        Some(&payer.pubkey()),
+    );
+    transaction.sign(&[&payer], recent_blockhash);
+    banks_client.process_transaction(transaction).await.unwrap();
+    assert_eq!(
+        banks_client.get_balance(destination).await.unwrap(),
+        20_000_000
+    );
+    assert!(banks_client.get_account(locked).await.unwrap().is_none());
+}